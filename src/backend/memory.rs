@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+use super::{Backend, Error, Options, Page, Result};
+
+fn normalize(path: &str) -> String {
+    format!("/{}", path.trim_matches('/'))
+}
+
+fn merge(target: &mut Value, patch: Value) {
+    let (target, patch) = match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => (target, patch),
+        (target, patch) => return *target = patch,
+    };
+    for (key, value) in patch {
+        match value {
+            Value::Null => {
+                target.remove(&key);
+            }
+            value => merge(target.entry(key).or_insert(Value::Null), value),
+        }
+    }
+}
+
+/// In-memory backend built from `{path: value}` records, like `test/sample-data.yaml`
+///
+/// Listing a path yields every record below it, keyed by path relative to its parent, like a
+/// server does: `/languages` lists `languages/go`
+#[derive(Default)]
+pub struct Memory {
+    records: BTreeMap<String, Value>,
+}
+
+impl Memory {
+    /// Load records from a YAML snapshot, as written by `save`
+    pub fn from_reader<R: std::io::Read>(reader: R) -> serde_yaml::Result<Self> {
+        let records: Map<String, Value> = serde_yaml::from_reader(reader)?;
+        Ok(records.into_iter().collect())
+    }
+
+    pub fn records(&self) -> &BTreeMap<String, Value> {
+        &self.records
+    }
+
+    fn children<'a>(&'a self, path: &str) -> impl Iterator<Item = (&'a String, &'a Value)> {
+        let prefix = match path {
+            "/" => path.to_owned(),
+            _ => path.to_owned() + "/",
+        };
+        self.records.range(prefix.clone()..).take_while(move |(key, _)| key.starts_with(&prefix))
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<(S, Value)> for Memory {
+    fn from_iter<T: IntoIterator<Item = (S, Value)>>(iter: T) -> Self {
+        Self { records: iter.into_iter().map(|(k, v)| (normalize(k.as_ref()), v)).collect() }
+    }
+}
+
+impl Backend for Memory {
+    fn get(&self, path: &str) -> Result<Value> {
+        let path = normalize(path);
+        self.records.get(&path).cloned().ok_or(Error::NotFound(path))
+    }
+
+    fn list(&self, path: &str, _: &Options) -> Result<Page> {
+        let path = normalize(path);
+        let skip = path.rfind('/').unwrap_or_default() + 1;
+        let map: Map<String, Value> =
+            self.children(&path).map(|(k, v)| (k[skip..].to_owned(), v.clone())).collect();
+        if map.is_empty() && path != "/" && !self.records.contains_key(&path) {
+            return Err(Error::NotFound(path));
+        }
+        Ok(Value::Object(map).into())
+    }
+
+    fn put(&mut self, path: &str, value: Value) -> Result<()> {
+        self.records.insert(normalize(path), value);
+        Ok(())
+    }
+
+    fn patch(&mut self, path: &str, value: Value) -> Result<()> {
+        let path = normalize(path);
+        let target = self.records.get_mut(&path).ok_or_else(|| Error::NotFound(path.clone()))?;
+        merge(target, value);
+        Ok(())
+    }
+
+    fn delete(&mut self, path: &str) -> Result<()> {
+        let path = normalize(path);
+        let children: Vec<String> = self.children(&path).map(|(k, _)| k.clone()).collect();
+        let found = self.records.remove(&path).is_some();
+        if !found && children.is_empty() {
+            return Err(Error::NotFound(path));
+        }
+        for child in children {
+            self.records.remove(&child);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value};
+
+    use super::{Backend, Memory};
+
+    fn sample() -> Memory {
+        let test_data = include_str!("../../test/sample-data.yaml");
+        let data: serde_json::Map<String, Value> = serde_yaml::from_str(test_data).unwrap();
+        data.into_iter().collect()
+    }
+
+    #[test]
+    fn test_list() {
+        let memory = sample();
+        let rust = memory.list("/languages/rust", &Default::default()).unwrap().value;
+        let expected = json!({"rust/applications/restcli": {"category": "ultility"}});
+        assert_eq!(expected, rust);
+        let go = memory.list("/languages/go/applications", &Default::default()).unwrap().value;
+        let keys: Vec<&String> = go.as_object().unwrap().keys().collect();
+        assert_eq!(vec!["applications/etcd", "applications/kubernetes"], keys);
+        assert_eq!(
+            8,
+            memory.list("/", &Default::default()).unwrap().value.as_object().unwrap().len()
+        );
+        assert!(memory.list("/languages/java", &Default::default()).is_err());
+    }
+
+    #[test]
+    fn test_write() {
+        let mut memory = sample();
+        memory.patch("/languages/go", json!({"GC": false, "typed": true})).unwrap();
+        assert_eq!(json!({"GC": false, "typed": true}), memory.get("/languages/go").unwrap());
+        memory.delete("/languages/go").unwrap();
+        assert!(memory.get("/languages/go/applications/etcd").is_err());
+        memory.put("/languages/zig", json!({"GC": false})).unwrap();
+        assert_eq!(6, memory.records().len());
+    }
+}
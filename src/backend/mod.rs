@@ -0,0 +1,127 @@
+mod memory;
+mod rest;
+
+use core::fmt;
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+pub use memory::Memory;
+pub use rest::Rest;
+
+#[derive(Debug)]
+pub enum Error {
+    Request(request::Error),
+    NotFound(String),
+    /// Invalid backend settings
+    Config(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Request(error) => write!(f, "{}", error),
+            Self::NotFound(path) => write!(f, "{} not found", path),
+            Self::Config(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    /// HTTP status responded, if failed by it
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::Request(error) => error.status().map(|status| status.as_u16()),
+            _ => None,
+        }
+    }
+}
+
+impl From<request::Error> for Error {
+    fn from(error: request::Error) -> Self {
+        Self::Request(error)
+    }
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Request settings of a collection
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Defaults to GET
+    pub method: Option<String>,
+    pub headers: BTreeMap<String, String>,
+    pub query: BTreeMap<String, String>,
+    pub body: Option<Value>,
+}
+
+/// A page of collection
+#[derive(Debug, Default)]
+pub struct Page {
+    pub value: Value,
+    /// Path or URL of next page, as `Link: <...>; rel="next"` header resolved against response URL
+    pub next: Option<String>,
+    /// HTTP status, if fetched over HTTP
+    pub status: Option<u16>,
+}
+
+impl From<Value> for Page {
+    fn from(value: Value) -> Self {
+        Self { value, ..Default::default() }
+    }
+}
+
+/// Source of the tree restcli navigates
+///
+/// Paths are always absolute, e.g. `/languages/rust`, though next page may be a full URL
+pub trait Backend {
+    /// Fetch a single entity
+    fn get(&self, path: &str) -> Result<Value>;
+    /// Fetch a collection, which is expected to be an object keyed by relative paths
+    fn list(&self, path: &str, options: &Options) -> Result<Page>;
+    /// Create or replace an entity
+    fn put(&mut self, path: &str, value: Value) -> Result<()>;
+    /// Partially update an entity
+    fn patch(&mut self, path: &str, value: Value) -> Result<()>;
+    /// Remove an entity
+    fn delete(&mut self, path: &str) -> Result<()>;
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::thread::{self, JoinHandle};
+
+    use tiny_http::{Server, SslConfig};
+
+    fn spawn<T, F>(server: Server, scheme: &str, handle: F) -> (String, JoinHandle<T>)
+    where
+        T: Send + 'static,
+        F: FnOnce(Server) -> T + Send + 'static,
+    {
+        let url = format!("{}://{}/", scheme, server.server_addr().to_ip().unwrap());
+        (url, thread::spawn(move || handle(server)))
+    }
+
+    /// URL of a server on a free local port, and thread handling it
+    pub fn serve<T, F>(handle: F) -> (String, JoinHandle<T>)
+    where
+        T: Send + 'static,
+        F: FnOnce(Server) -> T + Send + 'static,
+    {
+        spawn(Server::http("127.0.0.1:0").unwrap(), "http", handle)
+    }
+
+    /// As `serve` over TLS, with certificate `test/localhost.pem`
+    pub fn serve_tls<T, F>(handle: F) -> (String, JoinHandle<T>)
+    where
+        T: Send + 'static,
+        F: FnOnce(Server) -> T + Send + 'static,
+    {
+        let certificate = include_bytes!("../../test/localhost.pem").to_vec();
+        let private_key = include_bytes!("../../test/localhost.key").to_vec();
+        let server = Server::https("127.0.0.1:0", SslConfig { certificate, private_key });
+        spawn(server.unwrap(), "https", handle)
+    }
+}
@@ -0,0 +1,423 @@
+use core::cell::RefCell;
+use core::fmt;
+
+use request::blocking::{Client, ClientBuilder, RequestBuilder, Response};
+use request::header::HeaderMap;
+use request::header::HeaderName;
+use request::header::HeaderValue;
+use request::header::{ACCEPT, LINK};
+use request::tls::{Certificate, Identity, TlsInfo, Version};
+use request::{Method, Url};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::{Backend, Error, Options, Page, Result};
+use crate::config::{Auth, TLS};
+
+/// URL of `rel="next"` in `Link` header
+fn next_link(header: &str) -> Option<&str> {
+    for link in header.split(',') {
+        let (url, params) = link.split_once(';')?;
+        let url = url.trim().strip_prefix('<')?.strip_suffix('>')?;
+        let mut params = params.split(';').filter_map(|param| param.trim().split_once('='));
+        if params.any(|(key, value)| key == "rel" && value.trim_matches('"') == "next") {
+            return Some(url);
+        }
+    }
+    None
+}
+
+fn read(path: &str) -> core::result::Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("Read {} fail: {}", path, e))
+}
+
+fn is_pkcs12(path: &str) -> bool {
+    path.ends_with(".p12") || path.ends_with(".pfx")
+}
+
+fn identity(tls: &TLS, path: &str) -> core::result::Result<Identity, String> {
+    if is_pkcs12(path) {
+        let password = match &tls.client_cert_password {
+            Some(password) => password.resolve()?,
+            None => String::new(),
+        };
+        return Identity::from_pkcs12_der(&read(path)?, &password).map_err(|e| e.to_string());
+    }
+    let mut pem = read(path)?;
+    if let Some(key) = &tls.client_key {
+        pem.push(b'\n');
+        pem.extend(read(key)?);
+    }
+    Identity::from_pem(&pem).map_err(|e| e.to_string())
+}
+
+/// Lower case hex of SHA-256 fingerprint, as printed by `openssl x509 -fingerprint -sha256`
+fn fingerprint(pin: &str) -> core::result::Result<String, String> {
+    let hex = pin.replace(':', "").to_lowercase();
+    match hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        true => Ok(hex),
+        false => Err(format!("Invalid SHA-256 fingerprint {}", pin)),
+    }
+}
+
+fn configure(mut builder: ClientBuilder, tls: &TLS) -> core::result::Result<ClientBuilder, String> {
+    if let Some(path) = &tls.ca_file {
+        for certificate in Certificate::from_pem_bundle(&read(path)?).map_err(|e| e.to_string())? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    if let Some(path) = &tls.client_cert {
+        let identity = identity(tls, path)?;
+        // PEM identity is only supported by rustls, while PKCS#12 only by native-tls
+        builder = match is_pkcs12(path) {
+            true => builder.use_native_tls().identity(identity),
+            false => builder.use_rustls_tls().identity(identity),
+        };
+    }
+    if let Some(version) = &tls.min_version {
+        let version = match version.as_str() {
+            "1.0" => Version::TLS_1_0,
+            "1.1" => Version::TLS_1_1,
+            "1.2" => Version::TLS_1_2,
+            "1.3" => Version::TLS_1_3,
+            version => return Err(format!("Unknown TLS version {}", version)),
+        };
+        builder = builder.min_tls_version(version);
+    }
+    if !tls.pin_sha256.is_empty() {
+        builder = builder.tls_info(true);
+    }
+    if tls.insecure_skip_verify {
+        warn!("TLS certificate verification disabled");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    Ok(builder)
+}
+
+enum Credential {
+    Basic(String, String),
+    Bearer(String),
+    Header(HeaderName, HeaderValue),
+}
+
+impl fmt::Debug for Credential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Basic(username, _) => write!(f, "Basic({}, <redacted>)", username),
+            Self::Bearer(_) => write!(f, "Bearer(<redacted>)"),
+            Self::Header(name, _) => write!(f, "Header({}, <redacted>)", name),
+        }
+    }
+}
+
+impl Credential {
+    fn resolve(auth: &Auth) -> core::result::Result<Self, String> {
+        let credential = match auth {
+            Auth::Basic { username, password } => {
+                Self::Basic(username.clone(), password.resolve()?)
+            }
+            Auth::Bearer { token } => Self::Bearer(token.resolve()?),
+            Auth::Header { name, value } => {
+                let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| e.to_string())?;
+                let mut value =
+                    HeaderValue::try_from(value.resolve()?).map_err(|e| e.to_string())?;
+                value.set_sensitive(true);
+                Self::Header(name, value)
+            }
+        };
+        Ok(credential)
+    }
+}
+
+pub struct Rest {
+    url: String,
+    client: Client,
+    credential: Option<Credential>,
+    /// Fingerprints of pinned certificates, any certificate accepted if empty
+    pins: Vec<String>,
+    /// Origins of servers whose certificate checked before sending any request
+    verified: RefCell<Vec<String>>,
+    /// Sent with every request, overriding headers of collections
+    headers: HeaderMap,
+}
+
+impl Rest {
+    pub fn new(url: String, auth: Option<&Auth>, tls: Option<&TLS>) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        let mut builder = Client::builder().default_headers(headers);
+        if let Some(tls) = tls {
+            builder = configure(builder, tls).map_err(|e| Error::Config(format!("TLS: {}", e)))?;
+        }
+        let client = builder.build()?;
+        let pins = tls.map(|tls| tls.pin_sha256.iter().map(|pin| fingerprint(pin)).collect());
+        let pins = pins.transpose().map_err(|e| Error::Config(format!("TLS: {}", e)))?;
+        let credential = match auth {
+            Some(auth) => Some(Credential::resolve(auth).map_err(|e| {
+                Error::Config(format!("Resolve credential {:?} fail: {}", auth, e))
+            })?),
+            None => None,
+        };
+        debug!("Credential {:?}", credential);
+        let (pins, verified) = (pins.unwrap_or_default(), RefCell::default());
+        let headers = HeaderMap::new();
+        Ok(Self { url, client, credential, pins, verified, headers })
+    }
+
+    /// Send headers with every request, as given on command line
+    pub fn with_headers(mut self, headers: &[(String, String)]) -> Result<Self> {
+        for (name, value) in headers {
+            let name = HeaderName::from_bytes(name.as_bytes());
+            let name = name.map_err(|e| Error::Config(format!("Header {}", e)))?;
+            let value = HeaderValue::try_from(value);
+            let value = value.map_err(|e| Error::Config(format!("Header {}: {}", name, e)))?;
+            self.headers.insert(name, value);
+        }
+        Ok(self)
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = match path.starts_with("http://") || path.starts_with("https://") {
+            true => path.to_owned(),
+            false => self.url.clone() + path.trim_start_matches('/'),
+        };
+        trace!("{} {}", method, url);
+        let builder = self.client.request(method, url);
+        match &self.credential {
+            Some(Credential::Basic(username, password)) => {
+                builder.basic_auth(username, Some(password))
+            }
+            Some(Credential::Bearer(token)) => builder.bearer_auth(token),
+            Some(Credential::Header(name, value)) => builder.header(name, value),
+            None => builder,
+        }
+    }
+
+    /// Reject response of server whose certificate is not pinned
+    fn verify(&self, response: &Response) -> Result<()> {
+        if self.pins.is_empty() {
+            return Ok(());
+        }
+        let info = response.extensions().get::<TlsInfo>();
+        let fingerprint = match info.and_then(|info| info.peer_certificate()) {
+            Some(certificate) => format!("{:x}", Sha256::digest(certificate)),
+            None => return Err(Error::Config(format!("No certificate of {}", response.url()))),
+        };
+        if !self.pins.contains(&fingerprint) {
+            let message =
+                format!("Certificate of {} not pinned, sha256 {}", response.url(), fingerprint);
+            return Err(Error::Config(message));
+        }
+        Ok(())
+    }
+
+    /// Send request, checking certificate of each server by a bare request first if pinned
+    fn send(&self, builder: RequestBuilder) -> Result<Response> {
+        let request = builder.headers(self.headers.clone()).build()?;
+        let origin = request.url().origin().ascii_serialization();
+        if !self.pins.is_empty() && !self.verified.borrow().contains(&origin) {
+            self.verify(&self.client.head(&origin).send()?)?;
+            self.verified.borrow_mut().push(origin);
+        }
+        let response = self.client.execute(request)?;
+        self.verify(&response)?;
+        Ok(response.error_for_status()?)
+    }
+}
+
+impl Backend for Rest {
+    fn get(&self, path: &str) -> Result<Value> {
+        Ok(self.send(self.request(Method::GET, path))?.json()?)
+    }
+
+    fn list(&self, path: &str, options: &Options) -> Result<Page> {
+        let method = match options.method.as_ref() {
+            Some(method) => Method::from_bytes(method.to_uppercase().as_bytes())
+                .map_err(|_| Error::Config(format!("Invalid method {}", method)))?,
+            None => Method::GET,
+        };
+        let mut builder = self.request(method, path);
+        if !options.query.is_empty() {
+            builder = builder.query(&options.query);
+        }
+        for (name, value) in options.headers.iter() {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = options.body.as_ref() {
+            builder = builder.json(body);
+        }
+        let response = self.send(builder)?;
+        let link = response.headers().get(LINK).and_then(|link| link.to_str().ok());
+        let next = link.and_then(next_link).and_then(|next| response.url().join(next).ok());
+        // Credential is sent along with next page, which must not lead to another server
+        let origin = Url::parse(&self.url).ok().map(|url| url.origin());
+        if let Some(next) = next.as_ref().filter(|next| Some(next.origin()) != origin) {
+            let message = format!("Next page {} not on server {}", next, self.url);
+            return Err(Error::Config(message));
+        }
+        let next = next.map(|next| next.to_string());
+        let status = Some(response.status().as_u16());
+        Ok(Page { value: response.json()?, next, status })
+    }
+
+    fn put(&mut self, path: &str, value: Value) -> Result<()> {
+        self.send(self.request(Method::PUT, path).json(&value))?;
+        Ok(())
+    }
+
+    fn patch(&mut self, path: &str, value: Value) -> Result<()> {
+        self.send(self.request(Method::PATCH, path).json(&value))?;
+        Ok(())
+    }
+
+    fn delete(&mut self, path: &str) -> Result<()> {
+        self.send(self.request(Method::DELETE, path))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{next_link, Rest};
+    use crate::backend::test::{serve, serve_tls};
+    use crate::backend::{Backend, Options};
+    use crate::config::{Auth, Secret, TLS};
+
+    #[test]
+    fn test_tls() {
+        let url = "https://localhost/".to_owned();
+        let tls = TLS { min_version: Some("1.2".into()), ..Default::default() };
+        assert!(Rest::new(url.clone(), None, Some(&tls)).is_ok());
+        let tls = TLS { min_version: Some("2.0".into()), ..Default::default() };
+        assert!(Rest::new(url.clone(), None, Some(&tls)).is_err());
+        let tls = TLS { ca_file: Some("/nonexistent/ca.pem".into()), ..Default::default() };
+        assert!(Rest::new(url.clone(), None, Some(&tls)).is_err());
+        let tls = TLS { pin_sha256: vec!["AB:CD".into()], ..Default::default() };
+        assert!(Rest::new(url, None, Some(&tls)).is_err());
+    }
+
+    #[test]
+    fn test_pin() {
+        let (url, _) = serve(|server| {
+            for request in server.incoming_requests() {
+                request.respond(tiny_http::Response::from_string("{}")).unwrap();
+            }
+        });
+        let pin = ["0f"; 32].join(":");
+        let tls = TLS { pin_sha256: vec![pin], ..Default::default() };
+        let rest = Rest::new(url.clone(), None, Some(&tls)).unwrap();
+        assert!(rest.get("/ports").unwrap_err().to_string().starts_with("No certificate"));
+        assert_eq!(json!({}), Rest::new(url, None, None).unwrap().get("/ports").unwrap());
+    }
+
+    #[test]
+    fn test_pin_tls() {
+        let (url, handle) = serve_tls(|server| {
+            let mut methods = Vec::new();
+            for request in server.incoming_requests().take(4) {
+                methods.push(request.method().to_string());
+                request.respond(tiny_http::Response::from_string("{}")).unwrap();
+            }
+            methods
+        });
+        let pin = "6F:AB:76:6B:32:C2:A1:89:5D:CA:19:D3:8A:F9:C6:8C:\
+                   0A:F9:26:F3:FA:06:3C:DF:53:39:9D:7A:86:BE:41:D8";
+        let ca_file = Some("test/localhost.pem".to_owned());
+        let tls =
+            TLS { ca_file: ca_file.clone(), pin_sha256: vec![pin.into()], ..Default::default() };
+        let auth = Auth::Bearer { token: Secret::Plain("t".into()) };
+        let mut rest = Rest::new(url.clone(), Some(&auth), Some(&tls)).unwrap();
+        rest.put("/ports/1", json!({})).unwrap();
+        assert_eq!(json!({}), rest.get("/ports/1").unwrap());
+
+        let tls = TLS { ca_file, pin_sha256: vec![["0f"; 32].join(":")], ..Default::default() };
+        let mut rest = Rest::new(url, None, Some(&tls)).unwrap();
+        let error = rest.put("/ports/1", json!({})).unwrap_err().to_string();
+        assert!(error.starts_with("Certificate of"), "{}", error);
+        assert_eq!(vec!["HEAD", "PUT", "GET", "HEAD"], handle.join().unwrap());
+    }
+
+    #[test]
+    fn test_list_options() {
+        let (url, handle) = serve(|server| {
+            let mut request = server.recv().unwrap();
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let tenant = request.headers().iter().find(|h| h.field.equiv("X-Tenant"));
+            let tenant = tenant.map(|h| h.value.to_string());
+            let summary = (request.method().to_string(), request.url().to_owned(), tenant, body);
+            request.respond(tiny_http::Response::from_string("{}")).unwrap();
+            summary
+        });
+        let mut options = Options { method: Some("post".into()), ..Default::default() };
+        options.headers.insert("X-Tenant".into(), "blue".into());
+        options.query.insert("expand".into(), "true".into());
+        options.body = Some(json!({"filter": "up"}));
+        let rest = Rest::new(url, None, None).unwrap();
+        assert_eq!(json!({}), rest.list("/interfaces", &options).unwrap().value);
+        let (method, url, tenant, body) = handle.join().unwrap();
+        assert_eq!(("POST", "/interfaces?expand=true"), (method.as_str(), url.as_str()));
+        assert_eq!((Some("blue".into()), r#"{"filter":"up"}"#.into()), (tenant, body));
+    }
+
+    #[test]
+    fn test_headers() {
+        let (url, handle) = serve(|server| {
+            let mut tenants = Vec::new();
+            for request in server.incoming_requests().take(2) {
+                let tenant = request.headers().iter().filter(|h| h.field.equiv("X-Tenant"));
+                tenants.push(tenant.map(|h| h.value.to_string()).collect::<Vec<_>>());
+                request.respond(tiny_http::Response::from_string("{}")).unwrap();
+            }
+            tenants
+        });
+        let mut options = Options::default();
+        options.headers.insert("X-Tenant".into(), "blue".into());
+        let headers = [("X-Tenant".to_owned(), "red".to_owned())];
+        let mut rest = Rest::new(url, None, None).unwrap().with_headers(&headers).unwrap();
+        rest.list("/interfaces", &options).unwrap();
+        rest.delete("/interfaces/eth0").unwrap();
+        assert_eq!(vec![vec!["red"]; 2], handle.join().unwrap());
+    }
+
+    #[test]
+    fn test_list_next() {
+        let (url, _) = serve(|server| {
+            for (request, link) in server.incoming_requests().zip(["/items?page=2", "items?page=3"])
+            {
+                let link = format!("<{}>; rel=\"next\"", link);
+                let response = tiny_http::Response::from_string("{}");
+                let header = tiny_http::Header::from_bytes("Link", link).unwrap();
+                request.respond(response.with_header(header)).unwrap();
+            }
+        });
+        let host = url.trim_end_matches('/');
+        let rest = Rest::new(format!("{}/v1/", host), None, None).unwrap();
+        let next = rest.list("/ports", &Default::default()).unwrap().next;
+        assert_eq!(Some(format!("{}/items?page=2", host)), next);
+        let next = rest.list("/ports/", &Default::default()).unwrap().next;
+        assert_eq!(Some(format!("{}/v1/ports/items?page=3", host)), next);
+    }
+
+    #[test]
+    fn test_list_next_other_host() {
+        let (url, _) = serve(|server| {
+            let request = server.recv().unwrap();
+            let link = "<http://example.com/items?page=2>; rel=\"next\"";
+            let header = tiny_http::Header::from_bytes("Link", link).unwrap();
+            request.respond(tiny_http::Response::from_string("{}").with_header(header)).unwrap();
+        });
+        let rest = Rest::new(url, None, None).unwrap();
+        let error = rest.list("/ports", &Default::default()).unwrap_err().to_string();
+        assert!(error.starts_with("Next page http://example.com/items?page=2 not on server"));
+    }
+
+    #[test]
+    fn test_next_link() {
+        let header = r#"<https://api/ports?page=1>; rel="prev", </ports?page=3>; rel="next""#;
+        assert_eq!(Some("/ports?page=3"), next_link(header));
+        assert_eq!(None, next_link(r#"<https://api/ports?page=1>; rel="prev""#));
+    }
+}
@@ -1,118 +1,677 @@
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
 use std::io;
 use std::io::Write;
 
-use request::header::HeaderMap;
-use request::header::HeaderValue;
-use request::header::ACCEPT;
 use serde_json::Value;
 use termion::event;
 use termion::input::TermRead;
 
-use crate::config::API;
-use crate::format::Formatter;
+use crate::backend::{Backend, Error, Options, Page, Result};
+use crate::config::{JsonPath, Key, Paging, Sort, API};
+use crate::format::{singular, Formatter, FormatterOptions, Hints};
+use crate::output::Output;
+use crate::table;
+use crate::template::{self, Vars};
 
-struct Rest {
-    url: String,
-    headers: HeaderMap,
+type Records = Vec<(String, Value)>;
+
+/// Records queried from APIs
+struct Tree {
+    more: bool,
+    root: String,
+    /// In display order, records below a path following it contiguously
+    records: Records,
+    /// Positions of records in lexical order of path, for lookup
+    index: Vec<usize>,
+    /// Display prefixes of collections with pages left
+    truncated: Vec<String>,
+    /// Display hints of APIs, and index of hints of each record
+    hints: Vec<Hints>,
+    hinted: BTreeMap<String, usize>,
 }
 
-impl Rest {
-    fn get(&self, path: &str) -> request::Result<Value> {
-        let url = self.url.clone() + path.trim_start_matches('/');
-        let client = request::blocking::Client::new();
-        client.get(url).headers(self.headers.clone()).send()?.json()
+impl Tree {
+    fn hints(&self, path: &str) -> Option<&Hints> {
+        self.hinted.get(path).map(|&index| &self.hints[index])
+    }
+
+    /// Position in lexical order of path, or where it would be
+    fn search(&self, path: &str) -> std::result::Result<usize, usize> {
+        self.index.binary_search_by(|&i| self.records[i].0.as_str().cmp(path))
+    }
+
+    /// Path at position in lexical order
+    fn path(&self, position: usize) -> Option<&str> {
+        self.index.get(position).map(|&i| self.records[i].0.as_str())
+    }
+
+    /// Whether path is a record or has records below it
+    fn exists(&self, path: &str) -> bool {
+        let path = path.trim_end_matches('/');
+        if path.is_empty() || self.search(path).is_ok() {
+            return true;
+        }
+        // Siblings like `/a-b` sort between `/a` and `/a/...`, but not after `/a/`
+        let prefix = format!("{}/", path);
+        let index = self.search(&prefix).unwrap_or_else(|index| index);
+        self.path(index).map(|key| key.starts_with(&prefix)).unwrap_or_default()
+    }
+
+    /// Positions in lexical order of paths starting with prefix
+    fn range(&self, prefix: &str) -> std::ops::Range<usize> {
+        let start = self.search(prefix).unwrap_or_else(|e| e);
+        let end = self.index[start..].partition_point(|&i| self.records[i].0.starts_with(prefix));
+        start..start + end
     }
 }
 
-struct Querier<'a> {
-    rest: &'a Rest,
+/// Compare runs of digits by number and others as text
+fn natural(a: &str, b: &str) -> Ordering {
+    fn runs(text: &str) -> impl Iterator<Item = &str> {
+        let mut rest = text;
+        std::iter::from_fn(move || {
+            let digit = rest.chars().next()?.is_ascii_digit();
+            let end = rest.find(|c: char| c.is_ascii_digit() != digit).unwrap_or(rest.len());
+            let run;
+            (run, rest) = rest.split_at(end);
+            Some(run)
+        })
+    }
+    let is_number = |run: &str| run.starts_with(|c: char| c.is_ascii_digit());
+    let mut b_runs = runs(b);
+    for x in runs(a) {
+        let y = match b_runs.next() {
+            Some(y) => y,
+            None => return Ordering::Greater,
+        };
+        let ordering = match is_number(x) && is_number(y) {
+            true => {
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+            }
+            false => x.cmp(y),
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+    match b_runs.next() {
+        Some(_) => Ordering::Less,
+        None => a.cmp(b),
+    }
+}
+
+/// Pre-order of path segments, siblings ordered as sorted by the API found them
+///
+/// Siblings of different sorts are ranked lexical, natural then server, keeping order total
+fn display_order(orders: &BTreeMap<String, (Sort, usize)>, a: &str, b: &str) -> Ordering {
+    let segments = a.split('/').zip(b.split('/')).take_while(|(x, y)| x == y);
+    let common = segments.map(|(x, _)| x.len() + 1).sum::<usize>();
+    if common > a.len() || common > b.len() {
+        return a.len().cmp(&b.len());
+    }
+    let x = a[common..].split('/').next().unwrap_or_default();
+    let y = b[common..].split('/').next().unwrap_or_default();
+    let (x_order, y_order) =
+        (orders.get(&a[..common + x.len()]), orders.get(&b[..common + y.len()]));
+    let rank = |order: Option<&(Sort, usize)>| match order {
+        None | Some((Sort::Lexical, _)) => 0,
+        Some((Sort::Natural, _)) => 1,
+        Some((Sort::Server, _)) => 2,
+    };
+    let ordering = rank(x_order).cmp(&rank(y_order)).then_with(|| match (x_order, y_order) {
+        (Some((Sort::Natural, _)), Some((Sort::Natural, _))) => natural(x, y),
+        (Some((Sort::Server, i)), Some((Sort::Server, j))) => i.cmp(j),
+        _ => Ordering::Equal,
+    });
+    ordering.then_with(|| x.cmp(y)).then_with(|| a.cmp(b))
+}
+
+struct Querier<'a, B: Backend> {
+    backend: &'a B,
     apis: &'a [API],
     filter: &'a str,
     more: bool,
     root: Option<String>,
-    results: Vec<(String, Value)>,
+    results: Records,
+    truncated: Vec<String>,
+    hints: Vec<Hints>,
+    hinted: BTreeMap<String, usize>,
+    /// Sort of API found each record not sorted lexically, and its position in response
+    orders: BTreeMap<String, (Sort, usize)>,
 }
 
-impl<'a> Querier<'a> {
-    fn query_apis(&mut self, apis: &[API], prefix: String) -> request::Result<()> {
-        let mut more = false;
-        for api in apis {
-            let path = prefix.clone() + api.path.trim_start_matches('/');
-            let mut value = self.rest.get(&path)?;
+/// Headers and query parameters override inherited ones
+fn inherit(inherited: &Options, api: &API) -> Options {
+    let mut options = inherited.clone();
+    options.headers.extend(api.headers.iter().map(|(k, v)| (k.clone(), v.clone())));
+    for (key, value) in api.query.iter() {
+        let value = match value {
+            Value::String(string) => string.clone(),
+            value => value.to_string(),
+        };
+        options.query.insert(key.clone(), value);
+    }
+    options
+}
+
+/// JSONPath matches are always in an array, unwrap if matching the collection itself
+fn unwrap_matches(value: Value, keyed: bool) -> Value {
+    let mut values = match value {
+        Value::Array(values) if values.len() == 1 => values,
+        value => return value,
+    };
+    match (&values[0], keyed) {
+        (Value::Object(_), false) | (Value::Array(_), true) => values.pop().unwrap_or_default(),
+        _ => Value::Array(values),
+    }
+}
+
+/// Turn response into records keyed by relative path
+fn collect(value: Value, key: Option<&Key>, url: &str) -> Option<Records> {
+    let (items, key) = match (value, key) {
+        (Value::Object(object), _) => return Some(object.into_iter().collect()),
+        (Value::Array(items), Some(key)) => (items, key),
+        (Value::Array(_), None) => {
+            warn!("Array response of {} skipped, `key` required", url);
+            return None;
+        }
+        (_, _) => {
+            warn!("Unexpected response of {} skipped", url);
+            return None;
+        }
+    };
+    let (mut records, mut keys) = (Records::new(), BTreeSet::new());
+    for (index, item) in items.into_iter().enumerate() {
+        let key = match key.of(&item) {
+            Some(key) => key,
+            None => {
+                warn!("Item {} of {} skipped, key not found", index, url);
+                continue;
+            }
+        };
+        if !keys.insert(key.clone()) {
+            warn!("Item {} of {} skipped, key {} collides with earlier item", index, url, key);
+            continue;
+        }
+        records.push((key, item));
+    }
+    Some(records)
+}
+
+/// Token of next page found in response
+fn cursor(value: &Value, cursor: &JsonPath) -> Option<String> {
+    match jsonpath::find(&cursor.0, value) {
+        Value::Array(values) => match values.first()? {
+            Value::String(string) if !string.is_empty() => Some(string.clone()),
+            Value::Number(number) => Some(number.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Where APIs of an entity are queried
+#[derive(Clone, Default)]
+struct Scope {
+    /// Display path of entity
+    prefix: String,
+    /// URL of entity, which relative API paths appended to
+    url: String,
+    vars: Vars,
+    options: Options,
+}
+
+impl Scope {
+    fn root() -> Self {
+        Self { prefix: "/".into(), url: "/".into(), ..Default::default() }
+    }
+
+    /// URL and display prefix of API
+    fn locate(&self, api: &API) -> Result<(String, String)> {
+        let url = match template::is_template(&api.path) {
+            true => template::render(&api.path, &self.vars).map_err(Error::Config)?,
+            false => self.url.clone() + api.path.trim_start_matches('/'),
+        };
+        let display = match api.display_path() {
+            Some(display) => template::render(display, &self.vars).map_err(Error::Config)?,
+            None => return Ok((url, self.prefix.clone())),
+        };
+        match display.trim_matches('/') {
+            "" => Ok((url, "/".into())),
+            display => Ok((url, format!("/{}/", display))),
+        }
+    }
+
+    /// Display prefix and URL that keys of records found by API are appended to
+    ///
+    /// Items of arrays are keyed within the collection, so they are placed below its path,
+    /// unlike records of objects, keyed by path relative to the entity
+    fn place(&self, api: &API, url: &str, prefix: String, keyed: bool) -> (String, String) {
+        if !keyed {
+            return (prefix, self.url.clone());
+        }
+        let path = url.split_once('?').map(|(path, _)| path).unwrap_or(url).trim_end_matches('/');
+        let prefix = match api.display_path() {
+            Some(_) => prefix,
+            None => {
+                let relative = path.strip_prefix(self.url.as_str()).unwrap_or(path);
+                format!("{}{}/", prefix, relative.trim_matches('/'))
+            }
+        };
+        (prefix, format!("{}/", path))
+    }
+
+    /// Request settings of API
+    fn request(&self, api: &API) -> Options {
+        let (method, body) = (api.method.clone(), api.body.clone());
+        Options { method, body, ..inherit(&self.options, api) }
+    }
+
+    /// Scope of entity `key` found by API, displayed below `prefix` and located below `base`
+    fn enter(&self, api: &API, prefix: &str, base: &str, key: &str, value: &Value) -> Self {
+        let mut vars = self.vars.clone();
+        if let Some(name) = api.var.as_ref() {
+            template::bind(&mut vars, name, key, value);
+        }
+        let (prefix, url) = (format!("{}{}/", prefix, key), format!("{}{}/", base, key));
+        Self { prefix, url, vars, options: inherit(&self.options, api) }
+    }
+}
+
+impl<'a, B: Backend> Querier<'a, B> {
+    /// Fetch pages of collection into records, returns whether pages left, and whether records
+    /// are items of arrays, keyed within the collection
+    fn fetch(
+        &self,
+        api: &API,
+        url: &str,
+        mut options: Options,
+        records: &mut Records,
+    ) -> Result<(bool, bool)> {
+        let paging = api.pagination.as_ref().map(|p| &p.paging);
+        if let Some(Paging::Offset { offset, limit, size }) = paging {
+            options.query.insert(offset.clone(), "0".into());
+            options.query.insert(limit.clone(), size.to_string());
+        }
+        let max_pages = api.pagination.as_ref().map(|p| p.max_pages.max(1)).unwrap_or(1);
+        let (mut next_url, mut keys, mut keyed) = (url.to_owned(), BTreeSet::new(), false);
+        for page in 1..=max_pages {
+            let Page { mut value, next, .. } = self.backend.list(&next_url, &options)?;
+            let token = match paging {
+                Some(Paging::Cursor { cursor: jsonpath, .. }) => cursor(&value, jsonpath),
+                _ => None,
+            };
             if let Some(jsonpath) = api.jsonpath.as_ref() {
-                value = jsonpath::find(&jsonpath.0, &value);
+                value = unwrap_matches(jsonpath::find(&jsonpath.0, &value), api.key.is_some());
             }
-            let records = match value {
-                Value::Object(object) => object,
-                _ => continue,
+            keyed |= value.is_array();
+            let count = match &value {
+                Value::Array(items) => items.len(),
+                Value::Object(object) => object.len(),
+                _ => 0,
             };
+            for (key, value) in collect(value, api.key.as_ref(), &next_url).unwrap_or_default() {
+                if !keys.insert(key.clone()) {
+                    warn!("Record {} of {} skipped, collides with earlier page", key, next_url);
+                    continue;
+                }
+                records.push((key, value));
+            }
+            match (paging, next, token) {
+                (Some(Paging::Link), Some(next), _) => {
+                    (next_url, options.query) = (next, Default::default())
+                }
+                (Some(Paging::Cursor { param, .. }), _, Some(token)) => {
+                    options.query.insert(param.clone(), token);
+                }
+                (Some(Paging::Offset { offset, size, .. }), _, _) if count >= *size => {
+                    options.query.insert(offset.clone(), (page * size).to_string());
+                }
+                _ => return Ok((false, keyed)),
+            }
+        }
+        warn!("Collection {} truncated at {} pages", url, max_pages);
+        Ok((true, keyed))
+    }
+
+    fn query_apis(&mut self, apis: &[API], scope: &Scope) -> Result<()> {
+        let mut more = false;
+        for api in apis {
+            let (url, mut prefix) = scope.locate(api)?;
+            let mut records = Records::new();
+            let (truncated, keyed) = self.fetch(api, &url, scope.request(api), &mut records)?;
+            let base;
+            (prefix, base) = scope.place(api, &url, prefix, keyed);
+            if truncated {
+                self.truncated.push(prefix.clone());
+            }
             trace!("Found {} records", records.len());
-            let sub_apis = api.apis.as_ref().map(|v| v.as_slice()).unwrap_or_default();
-            for (key, value) in records.into_iter() {
-                let path = prefix.clone() + key.trim_matches('/');
-                self.results.push((path.clone(), value));
+            let hints = api.display.as_ref().map(|display| &display.hints);
+            let hints = hints.filter(|hints| !hints.is_empty()).map(|hints| {
+                self.hints.push(hints.clone());
+                self.hints.len() - 1
+            });
+            let sub_apis = api.apis.as_deref().unwrap_or_default();
+            for (position, (key, value)) in records.into_iter().enumerate() {
+                let key = key.trim_matches('/');
+                let path = prefix.clone() + key;
+                if let Some(index) = hints {
+                    self.hinted.insert(path.clone(), index);
+                }
+                if api.sort != Sort::Lexical {
+                    self.orders.insert(path.clone(), (api.sort, position));
+                }
                 if sub_apis.is_empty() {
+                    self.results.push((path, value));
                     continue;
                 }
                 more = true;
                 if api.is_entity != Some(true) || self.filter.starts_with(&path) {
-                    self.query_apis(sub_apis, path + "/")?;
+                    self.query_apis(sub_apis, &scope.enter(api, &prefix, &base, key, &value))?;
                 }
+                self.results.push((path, value));
             }
         }
         if self.root.is_none() {
-            (self.more, self.root) = (more, Some(prefix));
+            (self.more, self.root) = (more, Some(scope.prefix.clone()));
         }
         Ok(())
     }
 
-    fn query(mut self) -> request::Result<(bool, String, Vec<(String, Value)>)> {
-        self.query_apis(self.apis, "/".into())?;
-        self.results.sort_by(|a, b| a.0.cmp(&b.0));
-        Ok((self.more, self.root.unwrap_or("/".into()), self.results))
+    fn query(mut self) -> Result<Tree> {
+        self.query_apis(self.apis, &Scope::root())?;
+        let orders = &self.orders;
+        self.results.sort_by(|a, b| display_order(orders, &a.0, &b.0));
+        let mut index: Vec<usize> = (0..self.results.len()).collect();
+        index.sort_by(|&a, &b| self.results[a].0.cmp(&self.results[b].0));
+        let (more, root) = (self.more, self.root.unwrap_or("/".into()));
+        let (records, truncated) = (self.results, self.truncated);
+        let (hints, hinted) = (self.hints, self.hinted);
+        Ok(Tree { more, root, records, index, truncated, hints, hinted })
+    }
+
+    fn new(backend: &'a B, apis: &'a [API], filter: &'a str) -> Self {
+        let (more, root, results, truncated) = (false, None, Vec::new(), Vec::new());
+        let (hints, hinted, orders) = Default::default();
+        Self { backend, apis, filter, more, root, results, truncated, hints, hinted, orders }
+    }
+}
+
+/// Outcome of fetching an API
+pub struct Probe {
+    pub url: String,
+    /// HTTP status responded, if any
+    pub status: Option<u16>,
+    /// Shape of response
+    pub result: Result<String>,
+}
+
+fn probe_apis<B: Backend>(backend: &B, apis: &[API], scope: &Scope, probes: &mut Vec<Probe>) {
+    for api in apis {
+        let (url, prefix) = match scope.locate(api) {
+            Ok(located) => located,
+            Err(error) => {
+                probes.push(Probe { url: api.path.clone(), status: None, result: Err(error) });
+                continue;
+            }
+        };
+        let (mut value, status) = match backend.list(&url, &scope.request(api)) {
+            Ok(page) => (page.value, page.status),
+            Err(error) => {
+                probes.push(Probe { url, status: error.status(), result: Err(error) });
+                continue;
+            }
+        };
+        if let Some(jsonpath) = api.jsonpath.as_ref() {
+            value = unwrap_matches(jsonpath::find(&jsonpath.0, &value), api.key.is_some());
+        }
+        let shape = match &value {
+            Value::Object(object) => format!("object of {} records", object.len()),
+            Value::Array(items) => format!("array of {} items", items.len()),
+            value => format!("unexpected {}", value),
+        };
+        let (prefix, base) = scope.place(api, &url, prefix, value.is_array());
+        let records = collect(value, api.key.as_ref(), &url).unwrap_or_default();
+        let sub_apis = api.apis.as_deref().unwrap_or_default();
+        let entity = match records.into_iter().next() {
+            Some(entity) => entity,
+            None if !sub_apis.is_empty() => {
+                let shape = format!("{}, sub APIs not probed", shape);
+                probes.push(Probe { url, status, result: Ok(shape) });
+                continue;
+            }
+            None => (Default::default(), Value::Null),
+        };
+        probes.push(Probe { url, status, result: Ok(shape) });
+        if !sub_apis.is_empty() {
+            let scope = scope.enter(api, &prefix, &base, entity.0.trim_matches('/'), &entity.1);
+            probe_apis(backend, sub_apis, &scope, probes);
+        }
+    }
+}
+
+/// Fetch first page of each API, descending into first entity of collections
+pub fn probe<B: Backend>(backend: &B, apis: &[API]) -> Vec<Probe> {
+    let mut probes = Vec::new();
+    probe_apis(backend, apis, &Scope::root(), &mut probes);
+    probes
+}
+
+/// Connects to named servers, so that CLI may switch between them at runtime
+pub trait Connector<B: Backend> {
+    fn names(&self) -> Vec<String>;
+    fn connect(&self, name: &str) -> Result<(B, Vec<API>)>;
+
+    /// Keyword of items of arrays by name
+    fn keywords(&self) -> BTreeMap<String, String> {
+        BTreeMap::new()
+    }
+
+    /// Re-read configuration into a new connector, overrides applying to context in use
+    fn reload(&self, _context: &str) -> Result<Box<dyn Connector<B>>> {
+        Err(Error::Config("Reload not supported".into()))
     }
+}
 
-    fn new(rest: &'a Rest, apis: &'a [API], filter: &'a str) -> Self {
-        Self { rest, apis, filter, more: false, root: None, results: Vec::new() }
+/// Path of each API joined with its ancestors
+fn api_paths(apis: &[API], parent: &str, paths: &mut BTreeSet<String>) {
+    for api in apis {
+        let path = format!("{}{}", parent.trim_end_matches('/'), api.path);
+        api_paths(api.apis.as_deref().unwrap_or_default(), &path, paths);
+        paths.insert(path);
     }
 }
 
-pub struct CLI {
-    rest: Rest,
+pub struct CLI<B: Backend> {
+    backend: B,
+    connector: Option<Box<dyn Connector<B>>>,
+    context: Option<String>,
     apis: Vec<API>,
-    more: bool,
-    root: String,
-    records: Vec<(String, Value)>,
+    keywords: BTreeMap<String, String>,
+    output: Output,
+    options: FormatterOptions,
+    tree: Tree,
     current_path: String,
 }
 
-impl CLI {
-    pub fn new(url: String, apis: Vec<API>) -> request::Result<Self> {
-        let mut headers = HeaderMap::new();
-        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
-        let rest = Rest { url, headers };
-        let (more, root, records) = Querier::new(&rest, &apis, "/").query()?;
-        Ok(Self { rest, apis, more, root, records, current_path: "/".into() })
+impl<B: Backend> CLI<B> {
+    pub fn new(backend: B, apis: Vec<API>) -> Result<Self> {
+        let tree = Querier::new(&backend, &apis, "/").query()?;
+        let (connector, context, keywords) = (None, None, BTreeMap::new());
+        let (output, options, current_path) = (Output::default(), Default::default(), "/".into());
+        Ok(Self {
+            backend,
+            connector,
+            context,
+            apis,
+            keywords,
+            output,
+            options,
+            tree,
+            current_path,
+        })
+    }
+
+    /// Notation of `list`
+    pub fn with_formatter_options(mut self, options: FormatterOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Output of `list` unless piped to another
+    pub fn with_output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Start with named context, which may be switched by `context use`
+    pub fn with_connector(connector: Box<dyn Connector<B>>, context: &str) -> Result<Self> {
+        let (backend, apis) = connector.connect(context)?;
+        let mut cli = Self::new(backend, apis)?;
+        cli.keywords = connector.keywords();
+        (cli.connector, cli.context) = (Some(connector), Some(context.into()));
+        Ok(cli)
+    }
+
+    fn use_context(&mut self, name: &str) -> Result<()> {
+        let connector = match self.connector.as_ref() {
+            Some(connector) => connector,
+            None => return Err(Error::Config("Contexts not configured".into())),
+        };
+        let (backend, apis) = connector.connect(name)?;
+        self.tree = Querier::new(&backend, &apis, "/").query()?;
+        (self.backend, self.apis, self.context) = (backend, apis, Some(name.into()));
+        self.current_path = "/".into();
+        Ok(())
+    }
+
+    /// Reload config and re-query, staying at current path if still present
+    ///
+    /// Returns APIs added and removed
+    fn reload(&mut self) -> Result<(Vec<String>, Vec<String>)> {
+        let (connector, context) = match (self.connector.as_ref(), self.context.as_ref()) {
+            (Some(connector), Some(context)) => (connector, context),
+            _ => return Err(Error::Config("No config to reload".into())),
+        };
+        let connector = connector.reload(context)?;
+        let (backend, apis) = connector.connect(context)?;
+        // Querying along current path finds sub APIs of entities it goes through
+        let mut tree = Querier::new(&backend, &apis, &self.current_path).query()?;
+        let exists = tree.exists(&self.current_path);
+        if !exists {
+            tree = Querier::new(&backend, &apis, "/").query()?;
+        }
+        let (mut old, mut new) = (BTreeSet::new(), BTreeSet::new());
+        api_paths(&self.apis, "", &mut old);
+        api_paths(&apis, "", &mut new);
+        self.keywords = connector.keywords();
+        (self.connector, self.backend, self.apis, self.tree) =
+            (Some(connector), backend, apis, tree);
+        if !exists {
+            println!("{} no longer exists", self.current_path);
+            self.current_path = "/".into();
+        }
+        let added = new.difference(&old).cloned().collect();
+        Ok((added, old.difference(&new).cloned().collect()))
+    }
+
+    fn reload_command(&mut self) {
+        match self.reload() {
+            Ok((added, removed)) => {
+                added.iter().for_each(|path| println!("+ {}", path));
+                removed.iter().for_each(|path| println!("- {}", path));
+            }
+            Err(err) => eprintln!("Reload failed: {}", err),
+        }
+    }
+
+    fn context_command(&mut self, arg: &str) {
+        match arg.split_once(' ').unwrap_or((arg, "")) {
+            ("", _) => {
+                let names = self.connector.as_ref().map(|c| c.names()).unwrap_or_default();
+                for name in names {
+                    let active = Some(&name) == self.context.as_ref();
+                    println!("{} {}", if active { "*" } else { " " }, name);
+                }
+            }
+            ("use", name) => {
+                if let Some(err) = self.use_context(name).err() {
+                    eprintln!("Use context {} failed: {}", name, err)
+                }
+            }
+            (command, _) => eprintln!("Unknown context command {}", command),
+        }
     }
 
-    fn filter_records<'a>(&'a self) -> &'a [(String, Value)] {
+    fn prompt(&self) {
+        match self.context.as_ref() {
+            Some(context) => print!("restcli [{}] {}> ", context, self.current_path),
+            None => print!("restcli {}> ", self.current_path),
+        }
+        io::stdout().flush().unwrap();
+    }
+
+    /// Records at and below current path, in display order
+    fn filter_records(&self) -> &[(String, Value)] {
         if self.current_path == "/" {
-            return &self.records;
+            return &self.tree.records;
         }
         let path = &self.current_path;
-        let start = self.records.binary_search_by(|(key, _)| key.cmp(path)).unwrap_or_else(|e| e);
-        let end = self.records[start..].binary_search_by(|(key, _)| match key.starts_with(path) {
-            true => Ordering::Less,
-            false => key.cmp(path),
-        });
-        return &self.records[start..start + end.unwrap_or_else(|e| e)];
+        let directory = format!("{}/", path.trim_end_matches('/'));
+        let exact = self.tree.search(path).ok();
+        let positions = exact.into_iter().chain(self.tree.range(&directory));
+        // Records below a path follow it in display order, starting from the first found
+        let start = match positions.map(|position| self.tree.index[position]).min() {
+            Some(start) => start,
+            None => return &[],
+        };
+        let records = &self.tree.records[start..];
+        let below =
+            |(record, _): &&(String, Value)| record == path || record.starts_with(&directory);
+        &records[..records.iter().take_while(below).count()]
     }
 
-    fn refresh(&mut self) -> request::Result<()> {
-        let (rest, apis, path) = (&self.rest, &self.apis, &self.current_path);
-        (self.more, self.root, self.records) = Querier::new(rest, apis, path).query()?;
-        trace!("Root {} more {}", self.root, self.more);
+    /// List records, in output given by argument if any, optionally after `|`
+    fn list(&self, arg: &str) -> std::result::Result<(), String> {
+        let output = match arg.trim() {
+            "" => self.output.clone(),
+            arg => arg.strip_prefix('|').unwrap_or(arg).parse()?,
+        };
+        match output {
+            Output::Notation => (),
+            Output::Table(table) => {
+                let width = match termion::is_tty(&io::stdout()) {
+                    true => termion::terminal_size().ok().map(|(width, _)| width as usize),
+                    false => None,
+                };
+                let rows = table::rows(self.filter_records(), &self.current_path);
+                print!("{}", table.render(&rows, width));
+                return Ok(());
+            }
+            output => {
+                print!("{}", output.render(self.filter_records())?);
+                return Ok(());
+            }
+        }
+        let keywords = |name: &str| self.keywords.get(name).cloned().or_else(|| singular(name));
+        let formatter =
+            Formatter::new(self.filter_records(), keywords).with_options(self.options.clone());
+        println!("{}", formatter.with_hints(|path| self.tree.hints(path)));
+        for prefix in self.tree.truncated.iter() {
+            if prefix.starts_with(&self.current_path) || self.current_path.starts_with(prefix) {
+                println!("# {} truncated, more pages left", prefix);
+            }
+        }
+        Ok(())
+    }
+
+    fn refresh(&mut self) -> Result<()> {
+        let (backend, apis, path) = (&self.backend, &self.apis, &self.current_path);
+        self.tree = Querier::new(backend, apis, path).query()?;
+        trace!("Root {} more {}", self.tree.root, self.tree.more);
         Ok(())
     }
 
@@ -125,30 +684,28 @@ impl CLI {
             _ if arg.starts_with('/') => (0, arg),
             _ => (self.current_path.len(), arg),
         };
-        if truncate == self.current_path.len() && append == "" {
+        if truncate == self.current_path.len() && append.is_empty() {
             return;
         }
         let mut prefix = self.current_path[..truncate].to_owned();
-        if append != "" {
+        if !append.is_empty() {
             if !append.starts_with('/') && !prefix.ends_with('/') {
                 prefix.push('/');
             }
             prefix += append;
         }
-        if let Some(index) = self.records.binary_search_by(|(key, _)| key.cmp(&prefix)).err() {
-            if !prefix.ends_with('/') {
-                prefix.push('/');
-            }
-            if index >= self.records.len() || !self.records[index].0.starts_with(&prefix) {
-                println!("No such path");
-                return;
-            }
+        if !self.tree.exists(&prefix) {
+            println!("No such path");
+            return;
+        }
+        if self.tree.search(&prefix).is_err() && !prefix.ends_with('/') {
+            prefix.push('/');
         }
         self.current_path = prefix;
-        if append != "" && !self.more {
+        if !append.is_empty() && !self.tree.more {
             return;
         }
-        if append == "" && self.current_path.starts_with(&self.root) {
+        if append.is_empty() && self.current_path.starts_with(&self.tree.root) {
             return;
         }
         if let Some(err) = self.refresh().err() {
@@ -156,10 +713,16 @@ impl CLI {
         }
     }
 
+    /// Dump records as flat `path: value` YAML
+    fn save(&self, path: &str) -> std::result::Result<(), String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let records: serde_json::Map<String, Value> = self.tree.records.iter().cloned().collect();
+        serde_yaml::to_writer(file, &records).map_err(|e| e.to_string())
+    }
+
     pub fn run(&mut self) {
         let mut buf = Vec::new();
-        print!("restcli {}> ", self.current_path);
-        io::stdout().flush().unwrap();
+        self.prompt();
         for event in io::stdin().events() {
             let bytes = match event.unwrap() {
                 event::Event::Key(event::Key::Char(ch)) => {
@@ -177,16 +740,455 @@ impl CLI {
                 let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
                 match command {
                     "cd" => self.change_directory(arg),
+                    "context" => self.context_command(arg),
                     "list" => {
-                        println!("{}", Formatter::new(self.filter_records(), |_| None))
+                        if let Some(err) = self.list(arg).err() {
+                            eprintln!("List failed: {}", err)
+                        }
+                    }
+                    "reload" => self.reload_command(),
+                    "save" => {
+                        if let Some(err) = self.save(arg).err() {
+                            eprintln!("Save {} failed: {}", arg, err)
+                        }
                     }
                     "exit" => return,
                     line => eprintln!("Unknown command {}", line),
                 }
                 buf.clear();
-                print!("restcli {}> ", self.current_path);
-                io::stdout().flush().unwrap();
+                self.prompt();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::cmp::Ordering;
+
+    use serde_json::{json, Value};
+
+    use super::{collect, display_order, natural, probe, unwrap_matches, Connector, Querier, CLI};
+    use crate::backend::{Backend, Error, Memory, Options, Page, Result};
+    use crate::config::{Key, Sort, API};
+
+    fn records() -> serde_json::Map<String, Value> {
+        let test_data = include_str!("../test/sample-data.yaml");
+        serde_yaml::from_str(test_data).unwrap()
+    }
+
+    fn sample() -> CLI<Memory> {
+        let apis = vec![API { path: "/".into(), ..Default::default() }];
+        CLI::new(records().into_iter().collect(), apis).unwrap()
+    }
+
+    /// Each language as a context
+    struct Languages;
+
+    impl Connector<Memory> for Languages {
+        fn names(&self) -> Vec<String> {
+            vec!["go".into(), "rust".into()]
+        }
+
+        fn connect(&self, name: &str) -> Result<(Memory, Vec<API>)> {
+            if !self.names().iter().any(|n| n == name) {
+                return Err(Error::Config(format!("No such context {}", name)));
+            }
+            let prefix = format!("/languages/{}", name);
+            let records = records().into_iter().filter(|(k, _)| k.starts_with(&prefix));
+            Ok((records.collect(), vec![API { path: "/".into(), ..Default::default() }]))
+        }
+    }
+
+    /// Records list requests, responding pages told by a function, other requests not found
+    struct Stub<F> {
+        requests: RefCell<Vec<(String, Options)>>,
+        respond: F,
+    }
+
+    impl<F: Fn(&str, &Options) -> Page> Stub<F> {
+        fn new(respond: F) -> Self {
+            Self { requests: Default::default(), respond }
+        }
+    }
+
+    impl<F: Fn(&str, &Options) -> Page> Backend for Stub<F> {
+        fn get(&self, path: &str) -> Result<Value> {
+            Err(Error::NotFound(path.into()))
+        }
+
+        fn list(&self, path: &str, options: &Options) -> Result<Page> {
+            self.requests.borrow_mut().push((path.into(), options.clone()));
+            Ok((self.respond)(path, options))
+        }
+
+        fn put(&mut self, path: &str, _: Value) -> Result<()> {
+            Err(Error::NotFound(path.into()))
+        }
+
+        fn patch(&mut self, path: &str, _: Value) -> Result<()> {
+            Err(Error::NotFound(path.into()))
+        }
+
+        fn delete(&mut self, path: &str) -> Result<()> {
+            Err(Error::NotFound(path.into()))
+        }
+    }
+
+    /// Each list responding a single entity `a`
+    fn recorder() -> Stub<impl Fn(&str, &Options) -> Page> {
+        Stub::new(|_: &str, _: &Options| json!({"a": {}}).into())
+    }
+
+    #[test]
+    fn test_change_directory() {
+        let mut cli = sample();
+        assert_eq!(8, cli.filter_records().len());
+        cli.change_directory("languages");
+        assert_eq!("/languages/", cli.current_path);
+        cli.change_directory("go");
+        assert_eq!("/languages/go", cli.current_path);
+        assert_eq!(3, cli.filter_records().len());
+        cli.change_directory("..");
+        assert_eq!("/languages/", cli.current_path);
+        cli.change_directory("java");
+        assert_eq!("/languages/", cli.current_path);
+        cli.change_directory("/languages/rust/applications");
+        assert_eq!("/languages/rust/applications/", cli.current_path);
+        assert_eq!(1, cli.filter_records().len());
+    }
+
+    #[test]
+    fn test_save() {
+        let cli = sample();
+        let path = std::env::temp_dir().join("restcli-test-save.yaml");
+        cli.save(path.to_str().unwrap()).unwrap();
+        let memory = Memory::from_reader(std::fs::File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(path).ok();
+        let records: Vec<_> = memory.records().clone().into_iter().collect();
+        assert_eq!(cli.tree.records, records);
+    }
+
+    #[test]
+    fn test_use_context() {
+        let mut cli = CLI::with_connector(Box::new(Languages), "go").unwrap();
+        assert_eq!(3, cli.tree.records.len());
+        cli.change_directory("languages");
+        cli.use_context("rust").unwrap();
+        assert_eq!((Some("rust"), "/"), (cli.context.as_deref(), cli.current_path.as_str()));
+        assert_eq!(2, cli.tree.records.len());
+        assert!(cli.use_context("java").is_err());
+        assert_eq!(Some("rust"), cli.context.as_deref());
+    }
+
+    /// Serves sample records, replacing its API on each reload
+    struct Reloads(Vec<API>);
+
+    impl Connector<Memory> for Reloads {
+        fn names(&self) -> Vec<String> {
+            vec!["default".into()]
+        }
+
+        fn connect(&self, _: &str) -> Result<(Memory, Vec<API>)> {
+            Ok((records().into_iter().collect(), self.0.clone()))
+        }
+
+        fn reload(&self, _: &str) -> Result<Box<dyn Connector<Memory>>> {
+            let path = match self.0[0].path.as_str() {
+                "/" => "/languages",
+                "/languages" => "/languages/rust",
+                "/languages/rust" => "/{missing}",
+                _ => "/languages",
+            };
+            Ok(Box::new(Reloads(vec![API { path: path.into(), ..Default::default() }])))
+        }
+    }
+
+    /// Languages with applications below each, queried only within the language
+    fn entities(path: &str, _: &Options) -> Page {
+        match path {
+            "/languages" => json!({"languages/go": {}, "languages/rust": {}}).into(),
+            _ => json!({"applications/etcd": {}}).into(),
+        }
+    }
+
+    /// Serves `entities` with same APIs on each reload
+    struct Entities(Vec<API>);
+
+    impl Connector<Stub<fn(&str, &Options) -> Page>> for Entities {
+        fn names(&self) -> Vec<String> {
+            vec!["default".into()]
+        }
+
+        fn connect(&self, _: &str) -> Result<(Stub<fn(&str, &Options) -> Page>, Vec<API>)> {
+            Ok((Stub::new(entities), self.0.clone()))
+        }
+
+        fn reload(&self, _: &str) -> Result<Box<dyn Connector<Stub<fn(&str, &Options) -> Page>>>> {
+            Ok(Box::new(Entities(self.0.clone())))
+        }
+    }
+
+    #[test]
+    fn test_reload() {
+        let apis = vec![API { path: "/".into(), ..Default::default() }];
+        let mut cli = CLI::with_connector(Box::new(Reloads(apis)), "default").unwrap();
+        cli.change_directory("languages/go");
+        let (added, removed) = cli.reload().unwrap();
+        assert_eq!((vec!["/languages".to_string()], vec!["/".to_string()]), (added, removed));
+        assert_eq!("/languages/go", cli.current_path);
+        cli.reload().unwrap();
+        assert_eq!("/", cli.current_path);
+        // Failed reload keeps previous connector
+        assert!(cli.reload().is_err() && cli.reload().is_err());
+        assert_eq!((1, "/languages/rust"), (cli.tree.records.len(), cli.apis[0].path.as_str()));
+        assert!(sample().reload().is_err());
+
+        let yaml = "- {path: /languages, entity: true, apis: [{path: /applications}]}";
+        let apis: Vec<API> = serde_yaml::from_str(yaml).unwrap();
+        let mut cli = CLI::with_connector(Box::new(Entities(apis)), "default").unwrap();
+        cli.change_directory("languages");
+        cli.change_directory("go");
+        cli.change_directory("applications");
+        assert_eq!("/languages/go/applications/", cli.current_path);
+        cli.reload().unwrap();
+        assert_eq!("/languages/go/applications/", cli.current_path);
+        assert!(cli.tree.exists("/languages/go/applications/etcd"));
+    }
+
+    #[test]
+    fn test_exists() {
+        let data: Memory = [("/languages/go/a", json!({})), ("/languages/go-x/a", json!({}))]
+            .into_iter()
+            .collect();
+        let apis = vec![API { path: "/".into(), ..Default::default() }];
+        let tree = Querier::new(&data, &apis, "/").query().unwrap();
+        assert!(tree.exists("/languages/go") && tree.exists("/languages/go/"));
+        assert!(
+            tree.exists("/") && !tree.exists("/languages/g") && !tree.exists("/languages/go/b")
+        );
+    }
+
+    #[test]
+    fn test_nested_path() {
+        let apis = vec![API { path: "/languages/go/applications".into(), ..Default::default() }];
+        let data: Memory = records().into_iter().collect();
+        let tree = Querier::new(&data, &apis, "/").query().unwrap();
+        let paths: Vec<&str> = tree.records.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(vec!["/applications/etcd", "/applications/kubernetes"], paths);
+    }
+
+    #[test]
+    fn test_display_hints() {
+        let yaml = "
+            - path: /languages
+              display: {hide: [GC]}
+            - path: /languages/go/applications
+              display: /apps";
+        let apis: Vec<API> = serde_yaml::from_str(yaml).unwrap();
+        let data: Memory = records().into_iter().collect();
+        let tree = Querier::new(&data, &apis, "/").query().unwrap();
+        assert_eq!(vec!["GC"], tree.hints("/languages/go").unwrap().hide);
+        assert!(tree.hints("/apps/applications/etcd").is_none());
+    }
+
+    #[test]
+    fn test_inherit_options() {
+        let yaml = "
+            - path: /interfaces
+              method: POST
+              body: {state: up}
+              headers: {X-Tenant: blue}
+              query: {expand: true}
+              apis:
+                - path: /vlans
+                  headers: {X-Tenant: red}";
+        let apis: Vec<API> = serde_yaml::from_str(yaml).unwrap();
+        let recorder = recorder();
+        Querier::new(&recorder, &apis, "/").query().unwrap();
+        let requests = recorder.requests.into_inner();
+        let summary: Vec<_> = requests
+            .iter()
+            .map(|(path, o)| (path.as_str(), o.method.as_deref(), o.headers["X-Tenant"].as_str()))
+            .collect();
+        let expected = vec![("/interfaces", Some("POST"), "blue"), ("/a/vlans", None, "red")];
+        assert_eq!(expected, summary);
+        assert!(requests.iter().all(|(_, o)| o.query["expand"] == "true"));
+        assert_eq!(
+            (Some(json!({"state": "up"})), None),
+            (requests[0].1.body.clone(), requests[1].1.body.clone())
+        );
+    }
+
+    #[test]
+    fn test_template() {
+        let yaml = "
+            - path: /v2/langs
+              display: /languages
+              var: lang
+              apis:
+                - path: /v2/apps?lang={lang}
+                  display: /languages/{lang}/apps";
+        let apis: Vec<API> = serde_yaml::from_str(yaml).unwrap();
+        let recorder = recorder();
+        let records = Querier::new(&recorder, &apis, "/").query().unwrap().records;
+        let requests = recorder.requests.into_inner();
+        let urls: Vec<&str> = requests.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(vec!["/v2/langs", "/v2/apps?lang=a"], urls);
+        let paths: Vec<&str> = records.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(vec!["/languages/a", "/languages/a/apps/a"], paths);
+    }
+
+    #[test]
+    fn test_array_path() {
+        let yaml = "- {path: /interfaces, key: id, apis: [{path: /vlans}]}";
+        let apis: Vec<API> = serde_yaml::from_str(yaml).unwrap();
+        let stub = Stub::new(|path: &str, _: &Options| match path {
+            "/interfaces" => json!([{"id": "eth0"}, {"id": "eth1"}]).into(),
+            _ => json!({"10": {}}).into(),
+        });
+        let records = Querier::new(&stub, &apis, "/").query().unwrap().records;
+        let paths: Vec<&str> = records.iter().map(|(path, _)| path.as_str()).collect();
+        let expected = vec![
+            "/interfaces/eth0",
+            "/interfaces/eth0/10",
+            "/interfaces/eth1",
+            "/interfaces/eth1/10",
+        ];
+        assert_eq!(expected, paths);
+        let requests = stub.requests.take();
+        let urls: Vec<&str> = requests.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(vec!["/interfaces", "/interfaces/eth0/vlans", "/interfaces/eth1/vlans"], urls);
+        let urls: Vec<String> = probe(&stub, &apis).into_iter().map(|probe| probe.url).collect();
+        assert_eq!(vec!["/interfaces", "/interfaces/eth0/vlans"], urls);
+    }
+
+    #[test]
+    fn test_collect_array() {
+        let key: Key = serde_yaml::from_str("[name, $.vlan.id]").unwrap();
+        let value = json!([
+            {"name": "eth0", "vlan": {"id": 1}},
+            {"name": "eth0", "vlan": {"id": 2}},
+            {"name": "eth0", "vlan": {"id": 1}, "duplicated": true},
+            {"name": "eth1"},
+            {"name": "Gi1/0,2 #a", "vlan": {"id": 3}},
+        ]);
+        let records = collect(value, Some(&key), "/interfaces").unwrap();
+        let keys: Vec<&str> = records.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(vec!["eth0,1", "eth0,2", "Gi1%2F0%2C2%20%23a,3"], keys);
+        assert!(records[0].1.get("duplicated").is_none());
+        assert!(collect(json!([{"name": "eth0"}]), None, "/interfaces").is_none());
+        assert_eq!(json!([1]), unwrap_matches(json!([[1]]), true));
+        assert_eq!(json!([{"a": 1}]), unwrap_matches(json!([{"a": 1}]), true));
+        assert_eq!(json!({"a": 1}), unwrap_matches(json!([{"a": 1}]), false));
+    }
+
+    #[test]
+    fn test_pagination() {
+        let yaml = "
+            - path: /offset
+              display: /offset
+              jsonpath: $.items
+              key: id
+              pagination: {type: offset, size: 2}
+            - path: /cursor
+              display: /cursor
+              jsonpath: $.items
+              key: id
+              pagination: {type: cursor, cursor: $.next, param: cursor}
+            - path: /link
+              display: /link
+              jsonpath: $.items
+              key: id
+              pagination: {type: link, max-pages: 2}";
+        let apis: Vec<API> = serde_yaml::from_str(yaml).unwrap();
+        // Items 0 to 4 in pages of 2, starting from query `offset`, `cursor` or `start`
+        let pages = Stub::new(|path: &str, options: &Options| {
+            let start = path.split_once("?start=").map(|(_, start)| start.to_owned());
+            let start =
+                options.query.get("offset").or(options.query.get("cursor")).cloned().or(start);
+            let start: usize = start.unwrap_or_default().parse().unwrap_or_default();
+            let items: Vec<Value> =
+                (start..(start + 2).min(5)).map(|id| json!({"id": id})).collect();
+            let next = (start + 2 < 5).then(|| (start + 2).to_string());
+            let value = json!({"items": items, "next": next});
+            let next = next.map(|start| format!("/items?start={}", start));
+            Page { value, next, ..Default::default() }
+        });
+        let tree = Querier::new(&pages, &apis, "/").query().unwrap();
+        let count =
+            |prefix| tree.records.iter().filter(|(path, _)| path.starts_with(prefix)).count();
+        assert_eq!((5, 5, 4), (count("/offset/"), count("/cursor/"), count("/link/")));
+        assert_eq!(vec!["/link/".to_owned()], tree.truncated);
+    }
+
+    #[test]
+    fn test_sort() {
+        assert_eq!(Ordering::Less, natural("port2", "port10"));
+        assert_eq!(Ordering::Less, natural("a01", "a1"));
+        assert_eq!(Ordering::Greater, natural("b", "a10"));
+        let yaml = "
+            - {path: /lexical, display: /lexical, key: id}
+            - {path: /natural, display: /natural, key: id, sort: natural}
+            - {path: /server, display: /server, key: id, sort: server}";
+        let apis: Vec<API> = serde_yaml::from_str(yaml).unwrap();
+        let ports =
+            Stub::new(|_: &str, _: &Options| json!([{"id": 10}, {"id": 2}, {"id": 1}]).into());
+        let mut cli = CLI::new(ports, apis).unwrap();
+        let paths: Vec<&str> = cli.tree.records.iter().map(|(path, _)| path.as_str()).collect();
+        let expected = vec![
+            "/lexical/1",
+            "/lexical/10",
+            "/lexical/2",
+            "/natural/1",
+            "/natural/2",
+            "/natural/10",
+            "/server/10",
+            "/server/2",
+            "/server/1",
+        ];
+        assert_eq!(expected, paths);
+        cli.change_directory("/server/2");
+        assert_eq!("/server/2", cli.filter_records()[0].0);
+        cli.change_directory("/natural");
+        let paths: Vec<&str> = cli.filter_records().iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(vec!["/natural/1", "/natural/2", "/natural/10"], paths);
+        assert!(cli.tree.exists("/natural/10") && !cli.tree.exists("/natural/3"));
+
+        let orders = [
+            ("/p/3", (Sort::Natural, 0)),
+            ("/p/20", (Sort::Natural, 1)),
+            ("/p/10", (Sort::Server, 0)),
+            ("/p/2", (Sort::Server, 1)),
+        ];
+        let orders = orders.into_iter().map(|(path, order)| (path.to_owned(), order)).collect();
+        let mut paths = vec!["/p/2", "/p/10", "/p/20", "/p/3/x", "/p/3", "/p/b", "/p/a", "/p"];
+        paths.sort_by(|a, b| display_order(&orders, a, b));
+        let expected = vec!["/p", "/p/a", "/p/b", "/p/3", "/p/3/x", "/p/20", "/p/10", "/p/2"];
+        assert_eq!(expected, paths);
+        for (i, a) in paths.iter().enumerate() {
+            for (j, b) in paths.iter().enumerate() {
+                assert_eq!(i.cmp(&j), display_order(&orders, a, b), "{} {}", a, b);
             }
         }
     }
+
+    #[test]
+    fn test_probe() {
+        let yaml = "
+            - path: /languages
+              apis:
+                - path: /applications
+                - path: /{missing}";
+        let apis: Vec<API> = serde_yaml::from_str(yaml).unwrap();
+        let data: Memory = records().into_iter().collect();
+        let probes = probe(&data, &apis);
+        let urls: Vec<&str> = probes.iter().map(|probe| probe.url.as_str()).collect();
+        let expected = vec!["/languages", "/languages/C%2FC++/applications", "/{missing}"];
+        assert_eq!(expected, urls);
+        assert_eq!("object of 8 records", probes[0].result.as_ref().unwrap());
+        assert_eq!("object of 2 records", probes[1].result.as_ref().unwrap());
+        assert!(probes[2].result.is_err());
+    }
 }
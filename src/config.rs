@@ -1,7 +1,18 @@
+use core::fmt;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::str::FromStr;
+use std::{env, fs};
 
 use jsonpath::JsonPathInst;
+use serde::de::{DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::Value;
 
+use crate::format::{Hints, Theme};
+use crate::template;
+
+#[derive(Clone)]
 pub struct JsonPath(pub JsonPathInst);
 
 impl<'de> serde::Deserialize<'de> for JsonPath {
@@ -13,17 +24,1122 @@ impl<'de> serde::Deserialize<'de> for JsonPath {
     }
 }
 
-#[derive(Deserialize)]
+/// Field name, or JSONPath if starting with `$`
+#[derive(Clone)]
+pub enum KeyField {
+    Field(String),
+    Path(JsonPath),
+}
+
+impl<'de> serde::Deserialize<'de> for KeyField {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        if !text.starts_with('$') {
+            return Ok(Self::Field(text));
+        }
+        match JsonPathInst::from_str(&text) {
+            Ok(value) => Ok(Self::Path(JsonPath(value))),
+            Err(error) => Err(<D::Error as serde::de::Error>::custom(error)),
+        }
+    }
+}
+
+impl KeyField {
+    fn of(&self, item: &Value) -> Option<String> {
+        let value = match self {
+            Self::Field(name) => item.get(name)?.clone(),
+            Self::Path(path) => match jsonpath::find(&path.0, item) {
+                Value::Array(mut values) if values.len() == 1 => values.pop()?,
+                _ => return None,
+            },
+        };
+        template::encode(&value)
+    }
+}
+
+pub const KEY_SEPARATOR: &str = ",";
+
+/// Key of array items, fields of a composite key joined by `KEY_SEPARATOR`
+///
+/// Fields are percent-encoded, so that neither `/` nor `KEY_SEPARATOR` occurs in them
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Key {
+    Single(KeyField),
+    Composite(Vec<KeyField>),
+}
+
+impl Key {
+    pub fn of(&self, item: &Value) -> Option<String> {
+        match self {
+            Self::Single(field) => field.of(item),
+            Self::Composite(fields) => {
+                let keys: Option<Vec<String>> = fields.iter().map(|f| f.of(item)).collect();
+                Some(keys?.join(KEY_SEPARATOR))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Paging {
+    /// Follow `Link: <...>; rel="next"` header
+    Link,
+    /// Send token found in response by JSONPath `cursor` as query parameter `param`
+    Cursor { cursor: JsonPath, param: String },
+    /// Query parameters `offset` and `limit`, until page fewer than `size` items
+    Offset {
+        #[serde(default = "Paging::offset")]
+        offset: String,
+        #[serde(default = "Paging::limit")]
+        limit: String,
+        size: usize,
+    },
+}
+
+impl Paging {
+    fn offset() -> String {
+        "offset".into()
+    }
+
+    fn limit() -> String {
+        "limit".into()
+    }
+
+    /// Keys of paging of type `kind` besides `type`, unseen by serde_ignored as tagged
+    fn keys(kind: &str) -> Option<&'static [&'static str]> {
+        match kind {
+            "link" => Some(&[]),
+            "cursor" => Some(&["cursor", "param"]),
+            "offset" => Some(&["offset", "limit", "size"]),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Pagination {
+    #[serde(flatten)]
+    pub paging: Paging,
+    /// Collection is truncated when more pages left
+    #[serde(default = "Pagination::max_pages")]
+    pub max_pages: usize,
+}
+
+impl Pagination {
+    fn max_pages() -> usize {
+        100
+    }
+}
+
+/// Display order of records among siblings
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Sort {
+    #[default]
+    Lexical,
+    /// Runs of digits compared by number, `port2` before `port10`
+    Natural,
+    /// Order of items in array responses, lexical for object responses
+    Server,
+}
+
+#[derive(Clone, Default, Deserialize)]
+pub struct Display {
+    /// Display path of records, defaults to path of parent entity, may contain `{variable}`
+    pub path: Option<String>,
+    #[serde(flatten)]
+    pub hints: Hints,
+}
+
+impl From<&str> for Display {
+    fn from(path: &str) -> Self {
+        Self { path: Some(path.into()), ..Default::default() }
+    }
+}
+
+fn display<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Display>, D::Error> {
+    struct Visitor;
+
+    impl<'de> serde::de::Visitor<'de> for Visitor {
+        type Value = Display;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("display path or section")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, path: &str) -> Result<Display, E> {
+            Ok(path.into())
+        }
+
+        fn visit_map<A: serde::de::MapAccess<'de>>(self, map: A) -> Result<Display, A::Error> {
+            serde::Deserialize::deserialize(serde::de::value::MapAccessDeserializer::new(map))
+        }
+    }
+
+    deserializer.deserialize_any(Visitor).map(Some)
+}
+
+#[derive(Clone, Default, Deserialize)]
 pub struct API {
+    /// Appended to URL of parent entity, or from server root if containing `{variable}`
     pub path: String,
+    /// Display path and hints, or display path only
+    #[serde(default, deserialize_with = "display")]
+    pub display: Option<Display>,
+    /// Bind each key to `{var}` and fields to `{var.field}` for sub APIs
+    pub var: Option<String>,
     #[serde(rename = "entity")]
     pub is_entity: Option<bool>,
     pub jsonpath: Option<JsonPath>,
+    /// Turns array response into records
+    pub key: Option<Key>,
+    pub pagination: Option<Pagination>,
+    /// Headers also sent by sub APIs, unless overridden
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Query parameters also sent by sub APIs, unless overridden
+    #[serde(default)]
+    pub query: BTreeMap<String, Value>,
+    /// HTTP method fetching this API, GET if absent
+    pub method: Option<String>,
+    pub body: Option<Value>,
+    /// Display order of records found among their siblings
+    #[serde(default)]
+    pub sort: Sort,
     pub apis: Option<Vec<API>>,
 }
 
+/// Either a literal value or where to read it from
 #[derive(Deserialize)]
-pub struct Config {
+#[serde(untagged)]
+pub enum Secret {
+    Plain(String),
+    Env {
+        env: String,
+    },
+    File {
+        file: String,
+    },
+    /// Shell command whose output is the secret
+    Command {
+        command: String,
+    },
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Plain(_) => write!(f, "<redacted>"),
+            Self::Env { env } => write!(f, "env:{}", env),
+            Self::File { file } => write!(f, "file:{}", file),
+            Self::Command { command } => write!(f, "command:{}", command),
+        }
+    }
+}
+
+impl Secret {
+    pub fn resolve(&self) -> Result<String, String> {
+        let value = match self {
+            Self::Plain(value) => return Ok(value.clone()),
+            Self::Env { env } => std::env::var(env).map_err(|e| e.to_string())?,
+            Self::File { file } => std::fs::read_to_string(file).map_err(|e| e.to_string())?,
+            Self::Command { command } => {
+                let output = Command::new("sh").arg("-c").arg(command).output();
+                let output = output.map_err(|e| e.to_string())?;
+                if !output.status.success() {
+                    return Err(format!("{:?} exited with {}", self, output.status));
+                }
+                String::from_utf8(output.stdout).map_err(|e| e.to_string())?
+            }
+        };
+        Ok(value.trim_end_matches(['\r', '\n']).to_owned())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Auth {
+    Basic {
+        username: String,
+        password: Secret,
+    },
+    Bearer {
+        token: Secret,
+    },
+    /// Custom header like `X-API-Key`
+    Header {
+        name: String,
+        value: Secret,
+    },
+}
+
+impl Auth {
+    /// Keys of auth of type `kind` besides `type`, unseen by serde_ignored as tagged
+    fn keys(kind: &str) -> Option<&'static [&'static str]> {
+        match kind {
+            "basic" => Some(&["username", "password"]),
+            "bearer" => Some(&["token"]),
+            "header" => Some(&["name", "value"]),
+            _ => None,
+        }
+    }
+}
+
+/// Version either quoted or written as number, e.g. `1.2`
+fn version<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    struct Visitor;
+
+    impl<'de> serde::de::Visitor<'de> for Visitor {
+        type Value = String;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("version like 1.2")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, version: &str) -> Result<String, E> {
+            Ok(version.into())
+        }
+
+        fn visit_u64<E: serde::de::Error>(self, version: u64) -> Result<String, E> {
+            Ok(format!("{}.0", version))
+        }
+
+        fn visit_f64<E: serde::de::Error>(self, version: f64) -> Result<String, E> {
+            Ok(format!("{:.1}", version))
+        }
+    }
+
+    deserializer.deserialize_any(Visitor).map(Some)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TLS {
+    /// Extra root CA bundle in PEM
+    pub ca_file: Option<String>,
+    /// Client certificate in PEM, or PKCS#12 if named `*.p12` or `*.pfx`
+    pub client_cert: Option<String>,
+    /// Client private key in PEM, if not bundled in `client-cert`
+    pub client_key: Option<String>,
+    /// Password of PKCS#12 client certificate
+    pub client_cert_password: Option<Secret>,
+    /// One of 1.0, 1.1, 1.2 and 1.3
+    #[serde(default, deserialize_with = "version")]
+    pub min_version: Option<String>,
+    /// SHA-256 fingerprints of accepted server certificates in hex, optionally colon separated
+    #[serde(default)]
+    pub pin_sha256: Vec<String>,
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Context {
     pub url: String,
+    pub auth: Option<Auth>,
+    pub tls: Option<TLS>,
+    pub apis: Option<Vec<API>>,
+    /// Name of a shared tree in `api-trees`, used when `apis` absent
+    pub api_tree: Option<String>,
+}
+
+/// Context resolved against config
+pub struct Server<'a> {
+    pub url: &'a str,
+    pub auth: Option<&'a Auth>,
+    pub tls: Option<&'a TLS>,
+    pub apis: &'a [API],
+}
+
+pub const DEFAULT_CONTEXT: &str = "default";
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Files merged into this config, relative to it, entries of this config take precedence
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Server of default context
+    pub url: Option<String>,
+    pub auth: Option<Auth>,
+    pub tls: Option<TLS>,
+    #[serde(default)]
     pub apis: Vec<API>,
+    #[serde(default)]
+    pub api_trees: BTreeMap<String, Vec<API>>,
+    #[serde(default)]
+    pub contexts: BTreeMap<String, Context>,
+    pub current_context: Option<String>,
+    /// Keyword of items of arrays by name, overriding singular of the name
+    #[serde(default)]
+    pub keywords: BTreeMap<String, String>,
+    pub theme: Option<ThemeConfig>,
+    /// Files loaded into this config and their text, for locating keys
+    #[serde(skip)]
+    pub sources: Vec<(String, String)>,
+}
+
+/// Built-in theme by name, or colors overriding the default theme
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum ThemeConfig {
+    Named(String),
+    Custom(Theme),
+}
+
+impl API {
+    pub fn display_path(&self) -> Option<&str> {
+        self.display.as_ref().and_then(|display| display.path.as_deref())
+    }
+}
+
+impl Config {
+    /// Fill absent entries from another config
+    fn merge(&mut self, other: Config) {
+        self.url = self.url.take().or(other.url);
+        self.auth = self.auth.take().or(other.auth);
+        self.tls = self.tls.take().or(other.tls);
+        if self.apis.is_empty() {
+            self.apis = other.apis;
+        }
+        for (name, apis) in other.api_trees {
+            self.api_trees.entry(name).or_insert(apis);
+        }
+        for (name, context) in other.contexts {
+            self.contexts.entry(name).or_insert(context);
+        }
+        self.current_context = self.current_context.take().or(other.current_context);
+        for (name, keyword) in other.keywords {
+            self.keywords.entry(name).or_insert(keyword);
+        }
+        self.theme = self.theme.take().or(other.theme);
+        self.sources.extend(other.sources);
+    }
+
+    /// Override URL of a context, as given on command line
+    pub fn override_with(&mut self, context: &str, url: Option<&str>) {
+        if let Some(url) = url {
+            match self.contexts.get_mut(context) {
+                Some(context) => context.url = url.into(),
+                None => self.url = Some(url.into()),
+            }
+        }
+    }
+
+    pub fn theme(&self) -> Result<Theme, String> {
+        match self.theme.as_ref() {
+            None => Ok(Theme::default()),
+            Some(ThemeConfig::Named(name)) => {
+                Theme::named(name).ok_or_else(|| format!("No such theme {}", name))
+            }
+            Some(ThemeConfig::Custom(theme)) => Ok(theme.clone()),
+        }
+    }
+
+    pub fn context_names(&self) -> Vec<&str> {
+        let default = self.url.as_ref().map(|_| DEFAULT_CONTEXT);
+        default.into_iter().chain(self.contexts.keys().map(|k| k.as_str())).collect()
+    }
+
+    /// Name of context to use when not specified
+    pub fn current_context(&self) -> &str {
+        self.current_context.as_deref().unwrap_or(DEFAULT_CONTEXT)
+    }
+
+    pub fn server(&self, name: &str) -> Result<Server<'_>, String> {
+        if let Some(context) = self.contexts.get(name) {
+            let apis = match (&context.apis, &context.api_tree) {
+                (Some(apis), _) => apis.as_slice(),
+                (None, Some(tree)) => match self.api_trees.get(tree) {
+                    Some(apis) => apis.as_slice(),
+                    None => return Err(format!("No such API tree {}", tree)),
+                },
+                (None, None) => &self.apis,
+            };
+            let (auth, tls) = (context.auth.as_ref(), context.tls.as_ref());
+            return Ok(Server { url: &context.url, auth, tls, apis });
+        }
+        match (&self.url, name) {
+            (Some(url), DEFAULT_CONTEXT) => {
+                let (auth, tls) = (self.auth.as_ref(), self.tls.as_ref());
+                Ok(Server { url, auth, tls, apis: &self.apis })
+            }
+            _ => Err(format!("No such context {}", name)),
+        }
+    }
+}
+
+/// Deserializer telling fields of the struct deserialized from it by error
+struct Fields;
+
+#[derive(Debug)]
+struct FieldsError(&'static [&'static str]);
+
+impl fmt::Display for FieldsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fields {:?}", self.0)
+    }
+}
+
+impl std::error::Error for FieldsError {}
+
+impl serde::de::Error for FieldsError {
+    fn custom<T: fmt::Display>(_: T) -> Self {
+        Self(&[])
+    }
+}
+
+impl<'de> serde::Deserializer<'de> for Fields {
+    type Error = FieldsError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, FieldsError> {
+        Err(FieldsError(&[]))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value, FieldsError> {
+        Err(FieldsError(fields))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
+fn fields<T: DeserializeOwned>() -> &'static [&'static str] {
+    T::deserialize(Fields).err().map(|error| error.0).unwrap_or_default()
+}
+
+/// Keys of config, context, API, TLS and their sections, for hinting unknown keys
+fn known_keys() -> impl Iterator<Item = &'static str> {
+    let sections = [fields::<Hints>(), fields::<Theme>(), &["type", "max-pages"], SECRET_KEYS];
+    let fields = [fields::<Config>(), fields::<Context>(), fields::<API>(), fields::<TLS>()];
+    let tagged = ["link", "cursor", "offset"].map(Paging::keys);
+    let tagged = tagged.into_iter().chain(["basic", "bearer", "header"].map(Auth::keys));
+    fields.into_iter().chain(sections).chain(tagged.flatten()).flatten().copied()
+}
+
+/// Keys of a secret read from somewhere
+const SECRET_KEYS: &[&str] = &["env", "file", "command"];
+
+/// Push path of each key of mapping `value` not in any of `known`
+fn unknown_keys(
+    value: &serde_yaml::Value,
+    known: &[&[&str]],
+    path: &str,
+    unknown: &mut Vec<String>,
+) {
+    let keys = value.as_mapping().into_iter().flat_map(|map| map.keys());
+    for key in keys.filter_map(|key| key.as_str()) {
+        if !known.iter().any(|keys| keys.contains(&key)) {
+            unknown.push(format!("{}.{}", path, key));
+        }
+    }
+}
+
+fn unknown_in_auth(auth: &serde_yaml::Value, path: &str, unknown: &mut Vec<String>) {
+    let keys = match auth.get("type").and_then(|kind| Auth::keys(kind.as_str()?)) {
+        Some(keys) => keys,
+        None => return,
+    };
+    unknown_keys(auth, &[&["type"], keys], path, unknown);
+    for key in keys {
+        let secret = auth.get(key).unwrap_or(&serde_yaml::Value::Null);
+        unknown_keys(secret, &[SECRET_KEYS], &format!("{}.{}", path, key), unknown);
+    }
+}
+
+fn unknown_in_apis(apis: &serde_yaml::Value, path: &str, unknown: &mut Vec<String>) {
+    for (index, api) in apis.as_sequence().into_iter().flatten().enumerate() {
+        let path = format!("{}.{}", path, index);
+        if let Some(display) = api.get("display") {
+            let known = [&["path"], fields::<Hints>()];
+            unknown_keys(display, &known, &format!("{}.display", path), unknown);
+        }
+        if let Some(pagination) = api.get("pagination") {
+            let kind = pagination.get("type").and_then(|kind| kind.as_str());
+            if let Some(keys) = kind.and_then(Paging::keys) {
+                let known = [&["type", "max-pages"], keys];
+                unknown_keys(pagination, &known, &format!("{}.pagination", path), unknown);
+            }
+        }
+        if let Some(apis) = api.get("apis") {
+            unknown_in_apis(apis, &format!("{}.apis", path), unknown);
+        }
+    }
+}
+
+/// Push path of each unknown key in sections that are flattened, tagged or untagged, as
+/// serde_ignored does not see into them
+fn unknown_in_sections(config: &serde_yaml::Value, unknown: &mut Vec<String>) {
+    let servers = config.get("contexts").and_then(|contexts| contexts.as_mapping());
+    let servers = servers
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, context)| Some((format!("contexts.{}.", name.as_str()?), context)));
+    for (path, server) in [(String::new(), config)].into_iter().chain(servers) {
+        if let Some(auth) = server.get("auth") {
+            unknown_in_auth(auth, &format!("{}auth", path), unknown);
+        }
+        if let Some(secret) = server.get("tls").and_then(|tls| tls.get("client-cert-password")) {
+            let path = format!("{}tls.client-cert-password", path);
+            unknown_keys(secret, &[SECRET_KEYS], &path, unknown);
+        }
+        if let Some(apis) = server.get("apis") {
+            unknown_in_apis(apis, &format!("{}apis", path), unknown);
+        }
+    }
+    let trees = config.get("api-trees").and_then(|trees| trees.as_mapping());
+    for (name, apis) in trees.into_iter().flatten() {
+        let path = format!("api-trees.{}", name.as_str().unwrap_or_default());
+        unknown_in_apis(apis, &path, unknown);
+    }
+    if let Some(theme) = config.get("theme") {
+        unknown_keys(theme, &[fields::<Theme>()], "theme", unknown);
+    }
+}
+
+/// Known key that an unknown key is probably meant to be
+pub fn hint(unknown: &str) -> Option<&'static str> {
+    let key = unknown.rsplit('.').next().unwrap_or(unknown);
+    let normalize = |key: &str| key.replace(['-', '_'], "").to_lowercase();
+    let found = known_keys().find(|known| normalize(known) == normalize(key));
+    found.or_else(|| known_keys().find(|known| key.ends_with(&format!("-{}", known))))
+}
+
+/// Walks YAML along a key path, failing at the key found so that error tells its position
+struct Seek<'a>(&'a [&'a str]);
+
+impl<'de> DeserializeSeed<'de> for Seek<'_> {
+    type Value = ();
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Seek<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "key {}", self.0.join("."))
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(matched) = map.next_key_seed(Segment(self.0))? {
+            match matched {
+                true => map.next_value_seed(Seek(&self.0[1..]))?,
+                false => map.next_value::<IgnoredAny>().map(|_| ())?,
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let index: usize = match self.0[0].parse() {
+            Ok(index) => index,
+            Err(_) => return Ok(()),
+        };
+        for _ in 0..index {
+            seq.next_element::<IgnoredAny>()?;
+        }
+        match self.0.len() {
+            1 => seq.next_element_seed(Segment(&[])).map(|_| ()),
+            _ => seq.next_element_seed(Seek(&self.0[1..])).map(|_| ()),
+        }
+    }
+}
+
+/// Whether a map key matches first segment of path, failing if the path ends at it
+struct Segment<'a>(&'a [&'a str]);
+
+impl<'de> DeserializeSeed<'de> for Segment<'_> {
+    type Value = bool;
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<bool, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Segment<'_> {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "key {}", self.0.join("."))
+    }
+
+    fn visit_bool<E: serde::de::Error>(self, key: bool) -> Result<bool, E> {
+        self.visit_str(&key.to_string())
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, key: i64) -> Result<bool, E> {
+        self.visit_str(&key.to_string())
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, key: u64) -> Result<bool, E> {
+        self.visit_str(&key.to_string())
+    }
+
+    fn visit_str<E: serde::de::Error>(self, key: &str) -> Result<bool, E> {
+        match self.0 {
+            [segment] if *segment == key => Err(E::custom("found")),
+            [segment, ..] => Ok(*segment == key),
+            [] => Err(E::custom("found")),
+        }
+    }
+}
+
+/// Line and column of key at path like `apis.0.path` in YAML text
+fn position(text: &str, path: &str) -> Option<(usize, usize)> {
+    let segments: Vec<&str> = path.split('.').filter(|segment| *segment != "?").collect();
+    let deserializer = serde_yaml::Deserializer::from_str(text);
+    let location = Seek(&segments).deserialize(deserializer).err()?.location()?;
+    Some((location.line(), location.column()))
+}
+
+/// Where a key is found in config files
+#[derive(Clone, Debug, PartialEq)]
+pub struct Location {
+    pub file: String,
+    /// Line and column of key, unless not found
+    pub position: Option<(usize, usize)>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some((line, column)) => write!(f, "{}:{}:{}", self.file, line, column),
+            None => write!(f, "{}", self.file),
+        }
+    }
+}
+
+/// Substitute environment variables in string values, failing with key path of the value
+fn substitute(
+    value: &mut serde_yaml::Value,
+    env: &impl Fn(&str) -> Option<String>,
+    path: &mut Vec<String>,
+) -> Result<(), (String, String)> {
+    match value {
+        serde_yaml::Value::String(text) => {
+            *text = interpolate(text, env).map_err(|error| (path.join("."), error))?;
+        }
+        serde_yaml::Value::Sequence(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                path.push(index.to_string());
+                substitute(item, env, path)?;
+                path.pop();
+            }
+        }
+        serde_yaml::Value::Mapping(map) => {
+            for (key, value) in map.iter_mut() {
+                path.push(key.as_str().unwrap_or_default().to_owned());
+                substitute(value, env, path)?;
+                path.pop();
+            }
+        }
+        serde_yaml::Value::Tagged(tagged) => substitute(&mut tagged.value, env, path)?,
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Parse config with environment variables substituted in string values, also returning path
+/// of each unknown key
+pub fn parse(
+    text: &str,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<(Config, Vec<String>), String> {
+    let mut unknown = Vec::new();
+    // Parsed as is first, so that errors tell where they are
+    let deserializer = serde_yaml::Deserializer::from_str(text);
+    let callback = |path: serde_ignored::Path| unknown.push(path.to_string());
+    serde_ignored::deserialize::<_, _, Config>(deserializer, callback)
+        .map_err(|e| e.to_string())?;
+    let mut value: serde_yaml::Value = serde_yaml::from_str(text).map_err(|e| e.to_string())?;
+    unknown_in_sections(&value, &mut unknown);
+    if let Err((path, error)) = substitute(&mut value, &env, &mut Vec::new()) {
+        return Err(match position(text, &path) {
+            Some((line, column)) => format!("{} at line {} column {}", error, line, column),
+            None => error,
+        });
+    }
+    let config = serde_yaml::from_value(value).map_err(|e| e.to_string())?;
+    Ok((config, unknown))
+}
+
+/// Where config is looked up when not specified, in order
+pub fn candidates(env: impl Fn(&str) -> Option<String>) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = env("RESTCLI_CONFIG").into_iter().map(Into::into).collect();
+    paths.push(".restcli.yaml".into());
+    let xdg = env("XDG_CONFIG_HOME").map(PathBuf::from);
+    let home = env("HOME").map(|home| Path::new(&home).join(".config"));
+    paths.extend(xdg.or(home).map(|dir| dir.join("restcli").join("config.yaml")));
+    paths.push("/etc/restcli/config.yaml".into());
+    paths
+}
+
+/// Substitute `${NAME}` and `${NAME:-default}` in text with environment variables
+fn interpolate(text: &str, env: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let (mut output, mut remain) = (String::with_capacity(text.len()), text);
+    while let Some((left, right)) = remain.split_once("${") {
+        let (expression, right) = match right.split_once('}') {
+            Some(pair) => pair,
+            None => return Err(format!("Unclosed ${{ in {}", text)),
+        };
+        let (name, default) = match expression.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expression, None),
+        };
+        let value = match default {
+            Some(default) => env(name).filter(|v| !v.is_empty()).or(Some(default.into())),
+            None => env(name),
+        };
+        match value {
+            Some(value) => output.extend([left, &value]),
+            None => return Err(format!("Variable {} not set", name)),
+        }
+        remain = right;
+    }
+    output.push_str(remain);
+    Ok(output)
+}
+
+/// Failure loading a config file
+#[derive(Debug)]
+pub struct LoadError {
+    pub file: String,
+    pub message: String,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.file, self.message)
+    }
+}
+
+/// Unknown keys found in config files
+pub type UnknownKeys = Vec<(Location, String)>;
+
+fn load_file(
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    unknown: &mut UnknownKeys,
+) -> Result<Config, LoadError> {
+    let file = path.display().to_string();
+    let error = |message: String| LoadError { file: file.clone(), message };
+    let text = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+    let canonical = path.canonicalize().map_err(|e| error(e.to_string()))?;
+    if stack.contains(&canonical) {
+        return Err(error("Included recursively".into()));
+    }
+    let (mut config, keys) = parse(&text, |name| env::var(name).ok()).map_err(error)?;
+    for key in keys {
+        let location = Location { file: file.clone(), position: position(&text, &key) };
+        unknown.push((location, key));
+    }
+    config.sources.push((file, text));
+    stack.push(canonical);
+    let directory = path.parent().unwrap_or(Path::new(""));
+    for include in std::mem::take(&mut config.include) {
+        config.merge(load_file(&directory.join(include), stack, unknown)?);
+    }
+    stack.pop();
+    Ok(config)
+}
+
+/// Load config file with its includes and environment variables substituted
+pub fn load(path: &Path) -> Result<(Config, UnknownKeys), LoadError> {
+    let mut unknown = Vec::new();
+    let config = load_file(path, &mut Vec::new(), &mut unknown)?;
+    Ok((config, unknown))
+}
+
+/// Problem found by validation, and key path it is found at
+pub type Problems = Vec<(String, String)>;
+
+fn validate_apis(apis: &[API], path: &str, bound: &mut Vec<String>, errors: &mut Problems) {
+    for (index, api) in apis.iter().enumerate() {
+        let path = format!("{}.{}", path, index);
+        let templates = [("path", Some(api.path.as_str())), ("display", api.display_path())];
+        for (field, template) in templates.into_iter().filter_map(|(f, t)| Some((f, t?))) {
+            let names = match template::variables(template) {
+                Ok(names) => names,
+                Err(error) => {
+                    errors.push((format!("{}.{}", path, field), error));
+                    continue;
+                }
+            };
+            for name in names.into_iter().filter(|name| !bound.iter().any(|b| b == name)) {
+                let error = format!("variable {} not bound by any parent var", name);
+                errors.push((format!("{}.{}", path, field), error));
+            }
+        }
+        if let Some(apis) = api.apis.as_ref() {
+            bound.extend(api.var.clone());
+            validate_apis(apis, &format!("{}.apis", path), bound, errors);
+            bound.truncate(bound.len() - api.var.iter().count());
+        }
+    }
+}
+
+/// Push settings of APIs without effect
+fn warn_apis(apis: &[API], path: &str, warnings: &mut Problems) {
+    for (index, api) in apis.iter().enumerate() {
+        let path = format!("{}.{}", path, index);
+        if api.sort == Sort::Server && api.key.is_none() {
+            let warning = "server order is kept only with key, object responses are lexical";
+            warnings.push((format!("{}.sort", path), warning.into()));
+        }
+        if let Some(apis) = api.apis.as_ref() {
+            warn_apis(apis, &format!("{}.apis", path), warnings);
+        }
+    }
+}
+
+impl Config {
+    /// Settings that are valid but have no effect
+    pub fn warnings(&self) -> Problems {
+        let mut warnings = Problems::new();
+        warn_apis(&self.apis, "apis", &mut warnings);
+        for (name, apis) in self.api_trees.iter() {
+            warn_apis(apis, &format!("api-trees.{}", name), &mut warnings);
+        }
+        for (name, context) in self.contexts.iter() {
+            if let Some(apis) = context.apis.as_ref() {
+                warn_apis(apis, &format!("contexts.{}.apis", name), &mut warnings);
+            }
+        }
+        warnings
+    }
+
+    /// Problems that parsing does not catch
+    pub fn validate(&self) -> Problems {
+        let mut errors = Problems::new();
+        if self.url.is_none() && self.contexts.is_empty() {
+            errors.push(("url".into(), "neither url nor contexts specified".into()));
+        }
+        if let Some(name) = self.current_context.as_ref() {
+            if !self.context_names().contains(&name.as_str()) {
+                errors.push(("current-context".into(), format!("no such context {}", name)));
+            }
+        }
+        if let Some(error) = self.theme().err() {
+            errors.push(("theme".into(), error));
+        }
+        validate_apis(&self.apis, "apis", &mut Vec::new(), &mut errors);
+        for (name, apis) in self.api_trees.iter() {
+            validate_apis(apis, &format!("api-trees.{}", name), &mut Vec::new(), &mut errors);
+        }
+        for (name, context) in self.contexts.iter() {
+            let path = format!("contexts.{}", name);
+            if let Some(tree) =
+                context.api_tree.as_ref().filter(|t| !self.api_trees.contains_key(*t))
+            {
+                errors.push((format!("{}.api-tree", path), format!("no such API tree {}", tree)));
+            }
+            if let Some(apis) = context.apis.as_ref() {
+                validate_apis(apis, &format!("{}.apis", path), &mut Vec::new(), &mut errors);
+            }
+        }
+        errors
+    }
+
+    /// Location of key path, in the first loaded file having it
+    pub fn locate(&self, path: &str) -> Option<Location> {
+        self.sources.iter().find_map(|(file, text)| {
+            let position = position(text, path)?;
+            Some(Location { file: file.clone(), position: Some(position) })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::{candidates, hint, interpolate, load, parse, position, Auth, Config, Secret, TLS};
+    use crate::format::Color;
+
+    #[test]
+    fn test_secret() {
+        let yaml = "{type: basic, username: admin, password: {command: echo secret}}";
+        let (username, password) = match serde_yaml::from_str(yaml).unwrap() {
+            Auth::Basic { username, password } => (username, password),
+            _ => panic!("Not basic auth"),
+        };
+        assert_eq!("admin", username);
+        assert_eq!("secret", password.resolve().unwrap());
+        assert_eq!("command:echo secret", format!("{:?}", password));
+        let token: Secret = serde_yaml::from_str("token").unwrap();
+        assert_eq!("<redacted>", format!("{:?}", token));
+        let missing: Secret = serde_yaml::from_str("env: RESTCLI_TEST_MISSING").unwrap();
+        assert!(missing.resolve().is_err());
+    }
+
+    #[test]
+    fn test_context() {
+        let yaml = "
+            url: http://localhost/
+            apis: [{path: /}]
+            api-trees: {switch: [{path: /interfaces}, {path: /vlans}]}
+            contexts:
+              lab: {url: http://lab/, api-tree: switch}
+              edge: {url: http://edge/, apis: [], auth: {type: bearer, token: x}}
+            current-context: lab";
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(vec!["default", "edge", "lab"], config.context_names());
+        let server = config.server(config.current_context()).unwrap();
+        assert_eq!(("http://lab/", 2), (server.url, server.apis.len()));
+        assert_eq!(1, config.server("default").unwrap().apis.len());
+        assert!(config.server("edge").unwrap().auth.is_some());
+        assert!(config.server("core").is_err());
+        let mut config = config;
+        config.override_with("lab", Some("http://lab2/"));
+        assert_eq!("http://lab2/", config.server("lab").unwrap().url);
+
+        let versions: Vec<TLS> =
+            serde_yaml::from_str("[{min-version: 1.2}, {min-version: '1.3'}, {min-version: 1}]")
+                .unwrap();
+        let versions: Vec<_> = versions.iter().map(|tls| tls.min_version.as_deref()).collect();
+        assert_eq!(vec![Some("1.2"), Some("1.3"), Some("1.0")], versions);
+    }
+
+    #[test]
+    fn test_check() {
+        let yaml = "
+            url: http://localhost/
+            apis:
+              - path: /languages
+                var: lang
+                sub-apis: [{path: /apps}]
+                apis: [{path: '/v2/apps?lang={lang}'}, {path: '/{app}', display: '/{lang'}]
+            contexts:
+              lab: {url: http://lab/, api-tree: switch}
+            current-context: edge
+            theme: solarized";
+        let (config, unknown) = parse(yaml, |_| None).unwrap();
+        assert_eq!(vec!["apis.0.sub-apis"], unknown);
+        assert_eq!(Some("apis"), hint(&unknown[0]));
+        assert_eq!(Some("include"), hint("Include"));
+        assert_eq!(Some("theme"), hint("Theme"));
+        assert_eq!(Some((6, 17)), position(yaml, &unknown[0]));
+        let expected = vec![
+            ("current-context", "no such context edge"),
+            ("theme", "No such theme solarized"),
+            ("apis.0.apis.1.path", "variable app not bound by any parent var"),
+            ("apis.0.apis.1.display", "Unclosed variable in /{lang"),
+            ("contexts.lab.api-tree", "no such API tree switch"),
+        ];
+        let errors = config.validate();
+        let errors: Vec<(&str, &str)> =
+            errors.iter().map(|(k, e)| (k.as_str(), e.as_str())).collect();
+        assert_eq!(expected, errors);
+        let positions: Vec<_> = expected.iter().map(|(key, _)| position(yaml, key)).collect();
+        let expected = [(10, 13), (11, 13), (7, 57), (7, 73), (9, 39)];
+        assert_eq!(expected.map(Some).to_vec(), positions);
+        let (config, _) = parse("theme: {key: bright-red}", |_| None).unwrap();
+        assert_eq!(Color::BrightRed, config.theme().unwrap().key);
+        let yaml = "
+            apis: [{path: /, display: {hidee: [a]}, pagination: {type: link, max_pages: 3}}]
+            auth: {type: bearer, tokn: x, token: y}
+            contexts:
+              lab: {url: http://lab/, auth: {type: header, name: X-Key, value: {env: KEY, f: 1}}}
+            theme: {keys: red}";
+        let (config, unknown) = parse(yaml, |_| None).unwrap();
+        assert!(config.warnings().is_empty());
+        let expected = [
+            "auth.tokn",
+            "apis.0.display.hidee",
+            "apis.0.pagination.max_pages",
+            "contexts.lab.auth.value.f",
+            "theme.keys",
+        ];
+        assert_eq!(expected.to_vec(), unknown);
+        assert_eq!(Some("max-pages"), hint(&unknown[2]));
+        assert_eq!(Some((2, 78)), position(yaml, &unknown[2]));
+        let yaml = "
+            apis: [{path: /ports, sort: server, key: id, apis: [{path: /vlans, sort: server}]}]
+            api-trees: {switch: [{path: /ports, sort: natural}]}";
+        let (config, _) = parse(yaml, |_| None).unwrap();
+        let warnings = config.warnings();
+        assert_eq!(vec!["apis.0.apis.0.sort"], warnings.iter().map(|w| &w.0).collect::<Vec<_>>());
+        assert_eq!(Some((2, 80)), position(yaml, &warnings[0].0));
+        let error = parse("apis: [{path: /, jsonpath: '$[x'}]", |_| None).err().unwrap();
+        assert!(error.contains("line 1 column"));
+    }
+
+    #[test]
+    fn test_load() {
+        let env = |name: &str| (name == "HOST").then(|| "lab".to_string());
+        let expected = "url: http://lab:8080/";
+        assert_eq!(Ok(expected.into()), interpolate("url: http://${HOST}:${PORT:-8080}/", env));
+        let yaml = "# ${PORT} in comment\nurl: http://${HOST}/\napis: [{path: '/${PREFIX:-v1}'}]";
+        let (config, _) = parse(yaml, env).unwrap();
+        assert_eq!(
+            (Some("http://lab/"), "/v1"),
+            (config.url.as_deref(), config.apis[0].path.as_str())
+        );
+        let error = parse(&yaml.replace("${HOST}", "${HOST}:${PORT}"), env).err().unwrap();
+        assert_eq!("Variable PORT not set at line 2 column 1", error);
+        let paths = candidates(|name| (name == "XDG_CONFIG_HOME").then(|| "/xdg".to_string()));
+        let expected: Vec<PathBuf> = vec![
+            ".restcli.yaml".into(),
+            "/xdg/restcli/config.yaml".into(),
+            "/etc/restcli/config.yaml".into(),
+        ];
+        assert_eq!(expected, paths);
+
+        let directory = std::env::temp_dir().join(format!("restcli-test-{}", std::process::id()));
+        fs::create_dir_all(directory.join("shared")).unwrap();
+        let main = "{include: [shared/trees.yaml], url: http://localhost/, apis: [{path: /}]}";
+        fs::write(directory.join("config.yaml"), main).unwrap();
+        let shared = "{include: [../config.yaml], apis: [], api-trees: {switch: [{path: /vlans}]}}";
+        fs::write(directory.join("shared/trees.yaml"), shared).unwrap();
+        let error = load(&directory.join("config.yaml")).err().unwrap();
+        assert_eq!("Included recursively", error.message);
+        let shared = "{url: http://remote/, api-trees: {switch: [{path: /vlans}]}, tree: 1}";
+        fs::write(directory.join("shared/trees.yaml"), shared).unwrap();
+        let (config, unknown) = load(&directory.join("config.yaml")).unwrap();
+        assert_eq!(Some("http://localhost/"), config.url.as_deref());
+        assert_eq!((1, 1), (config.apis.len(), config.api_trees["switch"].len()));
+        assert!(unknown[0].0.file.ends_with("trees.yaml") && unknown[0].1 == "tree");
+        assert_eq!(Some((1, 62)), unknown[0].0.position);
+        fs::remove_dir_all(directory).unwrap();
+    }
 }
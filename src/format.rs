@@ -1,46 +1,475 @@
 use core::fmt;
 use core::fmt::{Display, Result};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::rc::Rc;
+use termion::color;
 use urlencoding::decode;
 
 use serde_json::{Map, Value};
+use smallvec::SmallVec;
 
-use crate::prefix::{Prefix, MAX_LEVEL};
+use crate::prefix::{Prefix, INLINE_LEVELS};
 
-const INDENT_WIDTH: usize = 2;
-
-struct Wrapper<'a>(&'a Value);
+struct Wrapper<'a>(&'a Value, &'a FormatterOptions);
 
 impl<'a> Display for Wrapper<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result {
+        let options = self.1;
         match &self.0 {
-            Value::String(string) => write!(f, "{}", string),
-            Value::Number(number) => write!(f, "{}", number),
-            Value::Bool(boolean) => write!(f, "{}", boolean),
+            Value::Null => write!(f, "{}", options.paint(Token::Null, &options.null)),
+            Value::String(string) if options.needs_quote(string) => {
+                let quoted = serde_json::to_string(string).map_err(|_| fmt::Error)?;
+                write!(f, "{}", options.paint(Token::String, quoted))
+            }
+            Value::String(string) => write!(f, "{}", options.paint(Token::String, string)),
+            Value::Number(number) => write!(f, "{}", options.paint(Token::Number, number)),
+            Value::Bool(boolean) => {
+                write!(f, "{}", options.paint(Token::Boolean, options.boolean(*boolean)))
+            }
             _ => Ok(()),
         }
     }
 }
 
-type KeywordsFn = fn(&str) -> Option<&str>;
+/// Terminal color by name
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Color {
+    None,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
 
-#[derive(Copy, Clone)]
-struct Context<'a> {
+impl Color {
+    /// Index in 16 ANSI colors
+    fn ansi(self) -> Option<u8> {
+        match self {
+            Self::None => None,
+            color => Some(color as u8 - 1),
+        }
+    }
+}
+
+/// Kind of text colored by theme
+#[derive(Clone, Copy)]
+enum Token {
+    Path,
+    Key,
+    String,
+    Number,
+    Boolean,
+    Null,
+}
+
+/// Color of each kind of text
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    pub path: Color,
+    pub key: Color,
+    pub string: Color,
+    pub number: Color,
+    pub boolean: Color,
+    pub null: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            path: Color::BrightBlue,
+            key: Color::Cyan,
+            string: Color::Green,
+            number: Color::Yellow,
+            boolean: Color::Magenta,
+            null: Color::BrightBlack,
+        }
+    }
+}
+
+impl Theme {
+    /// Built-in theme, `default` for dark terminals or `light`
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "light" => Some(Self {
+                path: Color::Blue,
+                key: Color::Black,
+                string: Color::Green,
+                number: Color::Red,
+                boolean: Color::Magenta,
+                null: Color::BrightBlack,
+            }),
+            _ => None,
+        }
+    }
+
+    fn color(&self, token: Token) -> Color {
+        match token {
+            Token::Path => self.path,
+            Token::Key => self.key,
+            Token::String => self.string,
+            Token::Number => self.number,
+            Token::Boolean => self.boolean,
+            Token::Null => self.null,
+        }
+    }
+}
+
+/// Text in ANSI color if any
+struct Paint<T: Display>(Option<u8>, T);
+
+impl<T: Display> Display for Paint<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result {
+        match self.0 {
+            Some(ansi) => {
+                let (color, reset) = (color::Fg(color::AnsiValue(ansi)), color::Fg(color::Reset));
+                write!(f, "{}{}{}", color, self.1, reset)
+            }
+            None => write!(f, "{}", self.1),
+        }
+    }
+}
+
+/// Characters of tree style guides
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Guides {
+    /// Box-drawing characters
+    Unicode,
+    Ascii,
+}
+
+impl Guides {
+    /// Guide of a line with siblings below, of the last sibling, and of a column passed by
+    fn parts(self) -> [&'static str; 3] {
+        match self {
+            Self::Unicode => ["├── ", "└── ", "│   "],
+            Self::Ascii => ["|-- ", "`-- ", "|   "],
+        }
+    }
+}
+
+/// Notation of formatter output
+#[derive(Clone, Debug)]
+pub struct FormatterOptions {
     indent_width: usize,
-    yesno: [&'static str; 2],
-    keywords: KeywordsFn,
-    key: &'a str,
-    indent: usize,
+    booleans: [String; 2],
+    null: String,
+    separator: String,
+    item_separator: String,
+    decode_paths: bool,
+    theme: Option<Theme>,
+    quote: bool,
+    guides: Option<Guides>,
 }
 
-impl Default for Context<'static> {
+impl Default for FormatterOptions {
     fn default() -> Self {
-        Self { indent_width: 2, yesno: ["yes", "no"], keywords: |_| None, indent: 0, key: "" }
+        Self {
+            indent_width: 2,
+            booleans: ["yes".into(), "no".into()],
+            null: String::new(),
+            separator: " ".into(),
+            item_separator: "!".into(),
+            decode_paths: true,
+            theme: None,
+            quote: false,
+            guides: None,
+        }
+    }
+}
+
+impl FormatterOptions {
+    /// Spaces indenting each level
+    pub fn indent_width(mut self, width: usize) -> Self {
+        self.indent_width = width;
+        self
+    }
+
+    /// Words of true and false
+    pub fn booleans(mut self, true_word: &str, false_word: &str) -> Self {
+        self.booleans = [true_word.into(), false_word.into()];
+        self
+    }
+
+    /// Printed after key of null, nothing by default
+    pub fn null(mut self, text: &str) -> Self {
+        self.null = text.into();
+        self
+    }
+
+    /// Between key and value
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Line around items of non-primitive arrays
+    pub fn item_separator(mut self, separator: &str) -> Self {
+        self.item_separator = separator.into();
+        self
+    }
+
+    /// Whether percent-encoded paths are decoded
+    pub fn decode_paths(mut self, decode: bool) -> Self {
+        self.decode_paths = decode;
+        self
+    }
+
+    /// Colors of output, none by default
+    pub fn theme(mut self, theme: Option<Theme>) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Whether strings are quoted when ambiguous, and multi-line ones printed as blocks
+    pub fn quote(mut self, quote: bool) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Tree style drawing guides instead of indenting, ignored by `StreamFormatter`
+    pub fn guides(mut self, guides: Option<Guides>) -> Self {
+        self.guides = guides;
+        self
+    }
+
+    /// String mistakable for other values or structure, or not fitting a line
+    fn needs_quote(&self, string: &str) -> bool {
+        if !self.quote {
+            return false;
+        }
+        let reserved = [&self.booleans[0], &self.booleans[1], &self.null, &self.item_separator];
+        string.is_empty()
+            || reserved.iter().any(|word| *word == string)
+            || string.starts_with(['!', '/', '.', '"'])
+            || string.contains(|c: char| c.is_whitespace() || c.is_control())
+            || string.parse::<f64>().is_ok()
+    }
+
+    fn paint<T: Display>(&self, token: Token, text: T) -> Paint<T> {
+        Paint(self.theme.as_ref().and_then(|theme| theme.color(token).ansi()), text)
+    }
+
+    fn boolean(&self, boolean: bool) -> &str {
+        &self.booleans[!boolean as usize]
     }
+
+    fn path(&self, path: &str) -> String {
+        match self.decode_paths {
+            true => decode_path(path),
+            false => path.to_owned(),
+        }
+    }
+}
+
+/// Multi-line string printable as block of its lines
+fn is_block(text: &str) -> bool {
+    let text = text.strip_suffix('\n').unwrap_or(text);
+    text.contains('\n') && !text.contains(|c: char| c.is_control() && c != '\n')
+}
+
+/// Keyword printed for each item of a primitive array instead of the array name
+pub type KeywordsFn<'a> = Box<dyn Fn(&str) -> Option<String> + 'a>;
+
+/// Plurals not following the rules, matched as suffix
+const IRREGULARS: &[(&str, &str)] = &[
+    ("children", "child"),
+    ("people", "person"),
+    ("indices", "index"),
+    ("vertices", "vertex"),
+    ("matrices", "matrix"),
+    ("analyses", "analysis"),
+    ("statuses", "status"),
+    ("aliases", "alias"),
+    ("caches", "cache"),
+    ("series", "series"),
+];
+
+/// Singular of an English plural noun, none if not plural
+pub fn singular(word: &str) -> Option<String> {
+    let lower = word.to_ascii_lowercase();
+    let replace =
+        |suffix: &str, to: &str| Some(format!("{}{}", &word[..word.len() - suffix.len()], to));
+    if let Some((plural, singular)) = IRREGULARS.iter().find(|(plural, _)| lower.ends_with(plural))
+    {
+        return replace(plural, singular);
+    }
+    match lower.as_str() {
+        w if w.len() > 4 && w.ends_with("ies") => replace("ies", "y"),
+        w if ["sses", "shes", "ches", "xes", "zzes"].iter().any(|s| w.ends_with(s)) => {
+            replace("es", "")
+        }
+        w if ["ss", "us", "is"].iter().any(|s| w.ends_with(s)) => None,
+        // Acronyms like `dns` have no vowel
+        w if w.ends_with('s') && w[..w.len() - 1].contains(['a', 'e', 'i', 'o', 'u', 'y']) => {
+            replace("s", "")
+        }
+        _ => None,
+    }
+}
+
+/// How fields of a record are printed
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Hints {
+    /// Fields not printed
+    #[serde(default)]
+    pub hide: Vec<String>,
+    /// Fields printed first in this order, followed by the others
+    #[serde(default)]
+    pub order: Vec<String>,
+    /// Label printed instead of field name
+    #[serde(default)]
+    pub rename: BTreeMap<String, String>,
+    /// Unit printed after value of field
+    #[serde(default)]
+    pub units: BTreeMap<String, String>,
+    /// Keyword of array items, also in nested values
+    #[serde(default)]
+    pub keywords: BTreeMap<String, String>,
+}
+
+impl Hints {
+    pub fn is_empty(&self) -> bool {
+        self.hide.is_empty()
+            && self.order.is_empty()
+            && self.rename.is_empty()
+            && self.units.is_empty()
+            && self.keywords.is_empty()
+    }
+
+    /// Visible fields of map in order
+    fn arrange<'a>(&self, map: &'a Map<String, Value>) -> Vec<(&'a String, &'a Value)> {
+        let ordered = self.order.iter().filter_map(|key| map.get_key_value(key));
+        let others = map.iter().filter(|(key, _)| !self.order.contains(key));
+        ordered.chain(others).filter(|(key, _)| !self.hide.contains(key)).collect()
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Context<'a> {
+    options: &'a FormatterOptions,
+    keywords: &'a dyn Fn(&str) -> Option<String>,
+    key: &'a str,
+    indent: usize,
+    /// Guides of columns left of lines in tree style, none at top level
+    guide: Option<&'a str>,
+    /// Lines at this level follow those printed in this context
+    more: bool,
+    /// Hints of record being printed
+    hints: Option<&'a Hints>,
+    /// Printing fields of record itself, where field hints apply
+    top: bool,
 }
 
 impl<'a> Context<'a> {
-    fn new(indent_width: usize, yesno: [&'static str; 2], keywords: KeywordsFn) -> Self {
-        Self { indent_width, yesno, keywords, ..Default::default() }
+    fn new(options: &'a FormatterOptions, keywords: &'a dyn Fn(&str) -> Option<String>) -> Self {
+        let (key, guide) = ("", None);
+        Self { options, keywords, key, indent: 0, guide, more: false, hints: None, top: true }
+    }
+
+    /// Guide of lines below a line at this level, which lines at its level follow if `more`
+    fn below(&self, more: bool) -> String {
+        match (self.options.guides, self.guide) {
+            (Some(guides), Some(guide)) if more => guide.to_owned() + guides.parts()[2],
+            (Some(_), Some(guide)) => guide.to_owned() + "    ",
+            _ => String::new(),
+        }
+    }
+
+    /// Context of lines below a line at this level, given guide of them by `below`
+    fn child<'b>(&self, guide: &'b str) -> Context<'b>
+    where
+        'a: 'b,
+    {
+        let indent = self.indent + self.options.indent_width;
+        Context { indent, guide: Some(guide), more: false, ..*self }
+    }
+
+    /// Line at this level, lines of text after the first continuing it
+    fn write(&self, f: &mut fmt::Formatter<'_>, more: bool, text: impl Display) -> Result {
+        let (guides, guide) = match (self.options.guides, self.guide) {
+            (Some(guides), Some(guide)) => (guides.parts(), guide),
+            _ => return writeln!(f, "{:2$}{}", "", text, self.indent),
+        };
+        let text = text.to_string();
+        let mut lines = text.split('\n');
+        let [branch, last, pass] = guides;
+        let (start, column) = if more { (branch, pass) } else { (last, "    ") };
+        writeln!(f, "{}{}{}", guide, start, lines.next().unwrap_or_default())?;
+        for line in lines {
+            match line.is_empty() {
+                true => writeln!(f, "{}", (guide.to_owned() + column).trim_end_matches(' '))?,
+                false => writeln!(f, "{}{}{}", guide, column, line)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Line of key and primitive value
+    fn line(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        more: bool,
+        key: impl Display,
+        value: &Value,
+        unit: Option<&str>,
+    ) -> Result {
+        let options = self.options;
+        if value.is_null() && options.null.is_empty() {
+            return self.write(f, more, key);
+        }
+        if let Some(text) = value.as_str().filter(|text| options.quote && is_block(text)) {
+            // Block chomping as in YAML, `|-` without final newline, `|+` keeping extra ones
+            let indicator = match text.strip_suffix('\n') {
+                Some(rest) if rest.ends_with('\n') => "|+",
+                Some(_) => "|",
+                None => "|-",
+            };
+            let mut block = match unit {
+                Some(unit) => format!("{}{}{} {}", key, options.separator, indicator, unit),
+                None => format!("{}{}{}", key, options.separator, indicator),
+            };
+            // Continuing line of key, not a line of its own in tree style
+            let indent = match options.guides {
+                Some(_) => options.indent_width,
+                None => self.indent + options.indent_width,
+            };
+            for line in text.lines() {
+                block.push('\n');
+                if !line.is_empty() {
+                    block += &format!("{:indent$}{}", "", options.paint(Token::String, line));
+                }
+            }
+            return self.write(f, more, block);
+        }
+        let (separator, value) = (&options.separator, Wrapper(value, options));
+        match unit {
+            Some(unit) if !value.0.is_null() => {
+                self.write(f, more, format_args!("{}{}{} {}", key, separator, value, unit))
+            }
+            _ => self.write(f, more, format_args!("{}{}{}", key, separator, value)),
+        }
+    }
+
+    fn keyword(&self) -> Option<String> {
+        let hinted = self.hints.and_then(|hints| hints.keywords.get(self.key)).cloned();
+        hinted.or_else(|| (self.keywords)(self.key))
     }
 }
 
@@ -50,10 +479,7 @@ trait IsPrimitive {
 
 impl IsPrimitive for Value {
     fn is_primitive(&self) -> bool {
-        match self {
-            Value::Array(_) | Value::Object(_) => false,
-            _ => true,
-        }
+        !matches!(self, Value::Array(_) | Value::Object(_))
     }
 }
 
@@ -61,48 +487,68 @@ trait Format {
     fn format<'a>(&self, f: &mut fmt::Formatter<'_>, ctx: Context<'a>) -> Result;
 }
 
+/// Non-primitive array items at indent, each between item separators
+fn format_items(array: &[Value], f: &mut fmt::Formatter<'_>, ctx: Context) -> Result {
+    let separator = &ctx.options.item_separator;
+    for item in array {
+        ctx.write(f, true, separator)?;
+        match item {
+            Value::Object(map) => {
+                map.format(f, Context { key: "", top: false, more: true, ..ctx })?
+            }
+            Value::Array(array) if array.iter().all(|v| v.is_primitive()) => {
+                for item in array {
+                    ctx.write(f, true, Wrapper(item, ctx.options))?;
+                }
+            }
+            Value::Array(array) => format_items(array, f, ctx.child(&ctx.below(true)))?,
+            item => ctx.write(f, true, Wrapper(item, ctx.options))?,
+        }
+    }
+    ctx.write(f, ctx.more, separator)
+}
+
 impl Format for Vec<Value> {
     fn format<'a>(&self, f: &mut fmt::Formatter<'_>, ctx: Context<'a>) -> Result {
-        if self.len() == 0 {
-            return Ok(());
+        let key = ctx.options.paint(Token::Key, ctx.key);
+        if self.is_empty() {
+            return ctx.write(f, ctx.more, key);
         }
         if self.as_slice().iter().all(|v| v.is_primitive()) {
-            let key = (ctx.keywords)(ctx.key).unwrap_or(ctx.key);
-            for item in self {
-                writeln!(f, "{:indent$}{} {}", "", key, Wrapper(item), indent = ctx.indent)?;
+            let keyword = ctx.keyword();
+            let key = ctx.options.paint(Token::Key, keyword.as_deref().unwrap_or(ctx.key));
+            for (i, item) in self.iter().enumerate() {
+                ctx.line(f, i + 1 < self.len() || ctx.more, &key, item, None)?;
             }
             return Ok(());
         }
-        writeln!(f, "{:indent$}{}", "", ctx.key, indent = ctx.indent)?;
-        let indent = ctx.indent + ctx.indent_width;
-        for item in self {
-            writeln!(f, "{:indent$}!", "", indent = indent)?;
-            writeln!(f, "{:indent$}{}", "", Wrapper(item), indent = indent)?;
-        }
-        writeln!(f, "{:indent$}!", "", indent = indent)
+        ctx.write(f, ctx.more, key)?;
+        format_items(self, f, ctx.child(&ctx.below(ctx.more)))
     }
 }
 
 impl Format for Map<String, Value> {
     fn format<'a>(&self, f: &mut fmt::Formatter<'_>, ctx: Context<'a>) -> Result {
-        for (key, value) in self {
+        let hints = ctx.hints.filter(|_| ctx.top);
+        let fields = match hints {
+            Some(hints) => hints.arrange(self),
+            None => self.iter().collect(),
+        };
+        let count = fields.len();
+        for (i, (key, value)) in fields.into_iter().enumerate() {
+            let label = hints.and_then(|h| h.rename.get(key)).unwrap_or(key);
+            let unit = hints.and_then(|h| h.units.get(key));
+            let ctx = Context { top: false, more: i + 1 < count || ctx.more, ..ctx };
             match value {
-                Value::Null => writeln!(f, "{:indent$}{}", "", key, indent = ctx.indent)?,
-                Value::Bool(boolean) => {
-                    let value = ctx.yesno[*boolean as usize];
-                    writeln!(f, "{:indent$}{} {}", "", key, value, indent = ctx.indent)?
-                }
-                Value::Number(number) => {
-                    writeln!(f, "{:indent$}{} {}", "", key, number, indent = ctx.indent)?
-                }
-                Value::String(string) => {
-                    writeln!(f, "{:indent$}{} {}", "", key, string, indent = ctx.indent)?
-                }
-                Value::Array(array) => array.format(f, Context { key, ..ctx })?,
+                Value::Array(array) => array.format(f, Context { key: label, ..ctx })?,
                 Value::Object(map) => {
-                    writeln!(f, "{:indent$}{}", "", key, indent = ctx.indent)?;
-                    let ctx = Context { indent: ctx.indent + ctx.indent_width, key: "", ..ctx };
-                    map.format(f, ctx)?;
+                    ctx.write(f, ctx.more, ctx.options.paint(Token::Key, label))?;
+                    map.format(f, Context { key: "", ..ctx.child(&ctx.below(ctx.more)) })?;
+                }
+                value => {
+                    let (label, unit) =
+                        (ctx.options.paint(Token::Key, label), unit.map(String::as_str));
+                    ctx.line(f, ctx.more, label, value, unit)?
                 }
             }
         }
@@ -111,24 +557,31 @@ impl Format for Map<String, Value> {
 }
 
 impl Format for Value {
+    /// Value of record, below key line if key is not empty, otherwise at level of context
     fn format<'a>(&self, f: &mut fmt::Formatter<'_>, ctx: Context<'a>) -> Result {
+        let key = ctx.options.paint(Token::Path, ctx.key);
         match self {
-            Value::Null => {
-                writeln!(f, "{:indent$}{}", "", ctx.key, indent = ctx.indent)
-            }
-            Value::Array(array) => {
-                let indent = ctx.indent;
-                for item in array {
-                    writeln!(f, "{:indent$}{} {}", "", ctx.key, Wrapper(item), indent = indent)?;
+            Value::Array(array) if array.iter().all(|v| v.is_primitive()) => {
+                for (i, item) in array.iter().enumerate() {
+                    ctx.line(f, i + 1 < array.len() || ctx.more, &key, item, None)?;
                 }
-                Ok(())
+                return Ok(());
             }
-            Value::Object(map) => {
-                if ctx.key != "" {
-                    writeln!(f, "{:indent$}{}", "", ctx.key, indent = ctx.indent)?;
-                }
-                map.format(f, Context { indent: ctx.indent + ctx.indent_width, ..ctx })
+            Value::Null if !ctx.key.is_empty() => return ctx.line(f, ctx.more, key, self, None),
+            Value::Array(_) | Value::Object(_) => (),
+            _ => return Ok(()),
+        }
+        let guide = ctx.below(ctx.more);
+        let ctx = match ctx.key.is_empty() {
+            true => ctx,
+            false => {
+                ctx.write(f, ctx.more, key)?;
+                ctx.child(&guide)
             }
+        };
+        match self {
+            Value::Array(array) => format_items(array, f, Context { top: false, ..ctx }),
+            Value::Object(map) => map.format(f, ctx),
             _ => Ok(()),
         }
     }
@@ -141,59 +594,316 @@ fn decode_path(path: &str) -> String {
     decode(path).unwrap_or_default().to_string()
 }
 
+type HintsFn<'a> = Box<dyn Fn(&str) -> Option<&'a Hints> + 'a>;
+
 pub struct Formatter<'a, S: AsRef<str>> {
     records: &'a [(S, Value)],
-    yesno: [&'static str; 2],
-    indent_width: usize,
-    keywords: fn(&str) -> Option<&str>,
+    options: FormatterOptions,
+    keywords: KeywordsFn<'a>,
+    hints: Option<HintsFn<'a>>,
 }
 
 impl<'a, S: AsRef<str>> Formatter<'a, S> {
-    pub fn new(records: &'a [(S, Value)], keywords: KeywordsFn) -> Self {
-        Self { records, yesno: ["yes", "no"], indent_width: 2, keywords }
+    pub fn new(records: &'a [(S, Value)], keywords: impl Fn(&str) -> Option<String> + 'a) -> Self {
+        let (options, keywords) = (FormatterOptions::default(), Box::new(keywords));
+        Self { records, options, keywords, hints: None }
+    }
+
+    pub fn with_options(mut self, options: FormatterOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Print fields of records as hinted by their path
+    pub fn with_hints(mut self, hints: impl Fn(&str) -> Option<&'a Hints> + 'a) -> Self {
+        self.hints = Some(Box::new(hints));
+        self
+    }
+}
+
+/// Headers of prefixes starting at a record, then the record below them
+struct RecordLines<'b> {
+    /// Context of enclosing prefixes, whose lines follow the record if `more`
+    ctx: Context<'b>,
+    /// Prefixes and whether lines at their level follow each
+    prefixes: &'b [(&'b str, bool)],
+    /// Length of enclosing prefixes
+    prefix_len: usize,
+    path: &'b str,
+    value: &'b Value,
+}
+
+impl<'b> Display for RecordLines<'b> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result {
+        let (options, mut prefix_len) = (self.ctx.options, self.prefix_len);
+        // Guide of lines below each prefix
+        let mut guides = SmallVec::<[String; INLINE_LEVELS]>::new();
+        for &(_, more) in self.prefixes {
+            let guide = guides.last().map(String::as_str).or(self.ctx.guide);
+            guides.push(Context { guide, ..self.ctx }.below(more));
+        }
+        let mut ctx = self.ctx;
+        for (&(text, more), guide) in self.prefixes.iter().zip(guides.iter()) {
+            prefix_len += text.len();
+            let mut text = options.path(text);
+            if prefix_len == self.path.len() {
+                text.push(':');
+            }
+            ctx.write(f, more, options.paint(Token::Path, text))?;
+            ctx = ctx.child(guide);
+        }
+        let ctx = Context { more: self.ctx.more, ..ctx };
+        if self.path.len() == prefix_len {
+            return self.value.format(f, ctx);
+        }
+        let path = self.path[prefix_len..].to_owned() + ":";
+        self.value.format(f, Context { key: &options.path(&path), ..ctx })
     }
 }
 
 impl<'a, S: AsRef<str>> Display for Formatter<'a, S> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result {
         let prefixes = Prefix::build(self.records.iter().map(|(key, _)| key.as_ref()));
-        let mut current = heapless::Vec::<&Prefix, MAX_LEVEL>::new();
+        let mut current = SmallVec::<[&Prefix; INLINE_LEVELS]>::new();
+        // Guide of lines below each current prefix
+        let mut guides = SmallVec::<[String; INLINE_LEVELS]>::new();
         let mut index = 0;
-        let mut ctx = Context::new(self.indent_width, self.yesno, self.keywords);
+        let indent_width = self.options.indent_width;
+        let mut ctx = Context::new(&self.options, &self.keywords);
         let mut prefix_len = 0;
-        for (i, record) in self.records.iter().enumerate() {
+        for (i, (path, value)) in self.records.iter().enumerate() {
             while i >= current.last().map(|p| p.range.end).unwrap_or(usize::MAX) {
                 let pop = current.pop().unwrap();
+                guides.pop();
                 prefix_len -= pop.text.len();
-                ctx.indent -= self.indent_width;
+                ctx.indent -= indent_width;
             }
+            let mut texts = SmallVec::<[(&str, bool); INLINE_LEVELS]>::new();
             while index < prefixes.len() && i >= prefixes[index].range.start {
-                let prefix = &prefixes[index];
-                prefix_len += prefix.text.len();
-                let mut prefix_text = decode_path(&prefix.text);
-                if prefix_len == record.0.as_ref().len() {
-                    prefix_text.push(':');
-                }
-                writeln!(f, "{:indent$}{}", "", prefix_text, indent = ctx.indent)?;
-                current.push(prefix).unwrap();
+                let end = current.last().map(|p| p.range.end).unwrap_or_default();
+                texts.push((prefixes[index].text, prefixes[index].range.end < end));
+                current.push(&prefixes[index]);
                 index += 1;
-                ctx.indent += INDENT_WIDTH;
             }
-            if record.0.as_ref().len() == prefix_len {
-                record.1.format(f, Context { indent: ctx.indent - self.indent_width, ..ctx })?;
-                continue;
+            let guide = guides.last().map(String::as_str);
+            let more = current.last().map(|p| p.range.end > i + 1).unwrap_or_default();
+            let (path, hints) =
+                (path.as_ref(), self.hints.as_ref().and_then(|hints| hints(path.as_ref())));
+            let lines = RecordLines {
+                ctx: Context { hints, guide, more, ..ctx },
+                prefixes: &texts,
+                prefix_len,
+                path,
+                value,
+            };
+            lines.fmt(f)?;
+            for &(_, more) in texts.iter() {
+                let guide = guides.last().map(String::as_str);
+                guides.push(Context { guide, ..ctx }.below(more));
+            }
+            prefix_len += texts.iter().map(|(text, _)| text.len()).sum::<usize>();
+            ctx.indent += indent_width * texts.len();
+        }
+        Ok(())
+    }
+}
+
+/// How a path segment is laid out, as decided by `Prefix::build`
+#[derive(Clone, Copy, PartialEq)]
+enum Layout {
+    /// Starts a prefix
+    Header,
+    /// Joins prefix of parent segment
+    Merged,
+    /// Part of record path after prefixes
+    Leaf,
+}
+
+/// Path segment shared by consecutive records
+struct Segment {
+    /// Where segment ends in paths of records
+    end: usize,
+    /// Records through segment
+    count: usize,
+    /// Parent segment has records not through this one
+    others: bool,
+    layout: Option<Layout>,
+    printed: bool,
+}
+
+type SegmentRef = Rc<RefCell<Segment>>;
+
+/// Segments of path and where each ends, as split by `Prefix::build`
+fn segments(path: &str) -> impl Iterator<Item = (&str, usize)> {
+    let mut offset = path.len() - path.trim_start_matches('/').len();
+    path.trim_matches('/').split('/').map(move |segment| {
+        let end = offset + segment.len();
+        offset = end + 1;
+        (segment, end)
+    })
+}
+
+/// Writes records pushed in order as `Formatter` would, holding only records whose
+/// prefixes depend on records not pushed yet
+///
+/// Records all through a segment are held until a record outside it, or `finish`, tells whether
+/// the segment is merged into its parent prefix, as output would differ otherwise. Memory is
+/// thus not bounded, e.g. for `/api/ports/0` to `/api/ports/N`, unless `with_prefix` declares
+/// `/api/ports`, holding then only records below one of its children.
+pub struct StreamFormatter<'a, W: io::Write> {
+    writer: W,
+    options: FormatterOptions,
+    keywords: KeywordsFn<'a>,
+    hints: Option<HintsFn<'a>>,
+    /// Path all records are at or below
+    prefix: String,
+    /// Path of last record and its segments
+    last: String,
+    open: Vec<SegmentRef>,
+    pending: VecDeque<(String, Value, Vec<SegmentRef>)>,
+    /// Last segment of each prefix written enclosing next record
+    printed: Vec<SegmentRef>,
+}
+
+impl<'a, W: io::Write> StreamFormatter<'a, W> {
+    pub fn new(writer: W, keywords: impl Fn(&str) -> Option<String> + 'a) -> Self {
+        let (options, keywords) = (FormatterOptions::default(), Box::new(keywords));
+        let (prefix, last, open, pending, printed) = Default::default();
+        Self { writer, options, keywords, hints: None, prefix, last, open, pending, printed }
+    }
+
+    pub fn with_options(mut self, options: FormatterOptions) -> Self {
+        self.options = FormatterOptions { guides: None, ..options };
+        self
+    }
+
+    /// Print fields of records as hinted by their path
+    pub fn with_hints(mut self, hints: impl Fn(&str) -> Option<&'a Hints> + 'a) -> Self {
+        self.hints = Some(Box::new(hints));
+        self
+    }
+
+    /// Declare that records pushed are all at or below `prefix`, e.g. `/api/ports`, so that each
+    /// is held only until a record outside the child of `prefix` it is through
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.trim_end_matches('/').into();
+        self
+    }
+
+    /// Decide layout of open segments from `depth` on, none of later records go through
+    fn close(&mut self, depth: usize) {
+        if let Some(segment) = self.open.get(depth) {
+            segment.borrow_mut().others = true;
+        }
+        for (i, segment) in self.open.iter().enumerate().skip(depth) {
+            let parent = i.checked_sub(1).and_then(|i| self.open[i].borrow().layout);
+            let mut segment = segment.borrow_mut();
+            if segment.layout.is_none() {
+                segment.layout = Some(match parent {
+                    Some(Layout::Leaf) => Layout::Leaf,
+                    _ if !segment.others => Layout::Merged,
+                    _ if segment.count > 1 => Layout::Header,
+                    _ => Layout::Leaf,
+                });
+            }
+        }
+        self.open.truncate(depth);
+    }
+
+    /// Paths must come in an order keeping paths below a path right after it, as for
+    /// `Prefix::build`, such as lexical or display order, and be below prefix of `with_prefix`
+    pub fn push(&mut self, path: String, value: Value) -> io::Result<()> {
+        let rest = path.strip_prefix(&self.prefix);
+        if !rest.map(|rest| rest.is_empty() || rest.starts_with('/')).unwrap_or_default() {
+            let error = format!("{} not below {}", path, self.prefix);
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, error));
+        }
+        let matched = match self.open.is_empty() {
+            true => 0,
+            false => {
+                segments(&self.last).zip(segments(&path)).take_while(|(a, b)| a.0 == b.0).count()
+            }
+        };
+        self.close(matched);
+        for segment in self.open.iter() {
+            let mut segment = segment.borrow_mut();
+            segment.count += 1;
+            if segment.layout.is_none() && segment.others && segment.count > 1 {
+                segment.layout = Some(Layout::Header);
+            }
+        }
+        for (depth, (_, end)) in segments(&path).enumerate().skip(matched) {
+            // No record is outside segments of declared prefix, so they join their parent
+            let layout = match depth {
+                0 => Some(Layout::Header),
+                _ if end <= self.prefix.len() => Some(Layout::Merged),
+                _ => None,
+            };
+            let others = depth > 0 && depth == matched;
+            let segment = Segment { end, count: 1, others, layout, printed: false };
+            self.open.push(Rc::new(RefCell::new(segment)));
+        }
+        self.pending.push_back((path.clone(), value, self.open.clone()));
+        self.last = path;
+        self.release()
+    }
+
+    /// Write records whose layout is decided
+    fn release(&mut self) -> io::Result<()> {
+        while let Some((_, _, segments)) = self.pending.front() {
+            if segments.iter().any(|segment| segment.borrow().layout.is_none()) {
+                break;
             }
-            let path = record.0.as_ref()[prefix_len..].to_owned() + ":";
-            record.1.format(f, Context { key: &decode_path(&path), ..ctx })?;
+            let (path, value, segments) = self.pending.pop_front().unwrap();
+            self.write(&path, &value, &segments)?;
         }
         Ok(())
     }
+
+    fn write(&mut self, path: &str, value: &Value, segments: &[SegmentRef]) -> io::Result<()> {
+        while let Some(last) = self.printed.last() {
+            if segments.iter().any(|segment| Rc::ptr_eq(segment, last)) {
+                break;
+            }
+            self.printed.pop();
+        }
+        let prefix_len = self.printed.last().map(|segment| segment.borrow().end).unwrap_or(0);
+        let indent = self.printed.len() * self.options.indent_width;
+        // Lines following are unknown yet, but not needed without guides
+        let mut texts = SmallVec::<[(&str, bool); INLINE_LEVELS]>::new();
+        for (i, segment) in segments.iter().enumerate() {
+            let (layout, printed) = (segment.borrow().layout, segment.borrow().printed);
+            match layout {
+                Some(Layout::Leaf) => break,
+                Some(Layout::Header) if !printed => (),
+                _ => continue,
+            }
+            let merged =
+                segments[i + 1..].iter().take_while(|s| s.borrow().layout == Some(Layout::Merged));
+            let last = merged.last().unwrap_or(segment);
+            let start = i.checked_sub(1).map(|i| segments[i].borrow().end).unwrap_or(0);
+            texts.push((&path[start..last.borrow().end], false));
+            segment.borrow_mut().printed = true;
+            self.printed.push(last.clone());
+        }
+        let hints = self.hints.as_ref().and_then(|hints| hints(path));
+        let ctx = Context { indent, hints, ..Context::new(&self.options, &self.keywords) };
+        let lines = RecordLines { ctx, prefixes: &texts, prefix_len, path, value };
+        write!(self.writer, "{}", lines)
+    }
+
+    /// Write records held back and return the writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.close(0);
+        self.release()?;
+        Ok(self.writer)
+    }
 }
 
 #[cfg(test)]
 mod test {
-    fn keywords(word: &str) -> Option<&str> {
-        Some(word.trim_end_matches('s'))
+    fn keywords(word: &str) -> Option<String> {
+        Some(word.trim_end_matches('s').into())
     }
 
     #[test]
@@ -208,4 +918,330 @@ mod test {
         let output = format!("{}", super::Formatter::new(entries.as_slice(), keywords));
         assert_eq!(include_str!("../test/sample-output.txt"), output);
     }
+
+    #[test]
+    fn test_single_record_groups() {
+        let paths = ["/a/b/c/d", "/a/e", "/b/c", "/d/e"];
+        let records: Vec<_> = paths.iter().map(|p| (*p, serde_json::json!({"k": 1}))).collect();
+        let output = format!("{}", super::Formatter::new(records.as_slice(), keywords));
+        let expected = "/a\n  \
+                        /b/c/d:\n    \
+                        k 1\n  \
+                        /e:\n    \
+                        k 1\n\
+                        /b/c:\n  \
+                        k 1\n\
+                        /d/e:\n  \
+                        k 1\n";
+        assert_eq!(expected, output);
+    }
+
+    #[test]
+    fn test_options() {
+        let records = vec![
+            ("/vlans/10%2F1", serde_json::json!({"up": true, "mtu": null, "tags": [false]})),
+            ("/vlans/20", serde_json::json!({"members": [{}], "up": false})),
+        ];
+        let options = super::FormatterOptions::default()
+            .indent_width(4)
+            .booleans("true", "false")
+            .null("-")
+            .separator(": ")
+            .item_separator("---")
+            .decode_paths(false);
+        let output = super::Formatter::new(records.as_slice(), |_| None).with_options(options);
+        let expected = "/vlans\n    \
+                        /10%2F1:\n        \
+                        mtu: -\n        \
+                        tags: false\n        \
+                        up: true\n    \
+                        /20:\n        \
+                        members\n            \
+                        ---\n            \
+                        ---\n        \
+                        up: false\n";
+        assert_eq!(expected, format!("{}", output));
+    }
+
+    #[test]
+    fn test_theme() {
+        let records = vec![("/vlans/10", serde_json::json!({"up": true, "name": null}))];
+        let theme: super::Theme = serde_yaml::from_str("{path: none, number: red}").unwrap();
+        assert_eq!(super::Color::Red, theme.number);
+        let options = super::FormatterOptions::default().theme(super::Theme::named("default"));
+        let output = super::Formatter::new(records.as_slice(), |_| None).with_options(options);
+        let expected = "\x1b[38;5;12m/vlans/10:\x1b[39m\n  \x1b[38;5;6mname\x1b[39m\n  \
+                        \x1b[38;5;6mup\x1b[39m \x1b[38;5;5myes\x1b[39m\n";
+        assert_eq!(expected, format!("{}", output));
+    }
+
+    #[test]
+    fn test_arrays() {
+        let value = serde_json::json!({
+            "members": [{"name": "eth0", "vlans": [10, 20]}, {"name": "eth1", "lag": {"id": 1}}],
+            "matrix": [[1, 2], [[3], {"x": 4}], []],
+            "empty": [],
+            "none": {},
+        });
+        let records = vec![
+            ("/switches/sw1", value),
+            ("/ports", serde_json::json!([{"id": 1}, 2, {}])),
+            ("/void", serde_json::json!({})),
+        ];
+        let output = format!("{}", super::Formatter::new(records.as_slice(), keywords));
+        let expected = "/switches/sw1:\n  \
+                        empty\n  \
+                        matrix\n    \
+                        !\n    \
+                        1\n    \
+                        2\n    \
+                        !\n      \
+                        !\n      \
+                        3\n      \
+                        !\n      \
+                        x 4\n      \
+                        !\n    \
+                        !\n    \
+                        !\n  \
+                        members\n    \
+                        !\n    \
+                        name eth0\n    \
+                        vlan 10\n    \
+                        vlan 20\n    \
+                        !\n    \
+                        lag\n      \
+                        id 1\n    \
+                        name eth1\n    \
+                        !\n  \
+                        none\n\
+                        /ports:\n  \
+                        !\n  \
+                        id 1\n  \
+                        !\n  \
+                        2\n  \
+                        !\n  \
+                        !\n\
+                        /void:\n";
+        assert_eq!(expected, output);
+    }
+
+    #[test]
+    fn test_quote() {
+        let value = serde_json::json!({
+            "name": "eth 0", "mode": "no", "id": "10", "path": "/a", "plain": "trunk",
+            "note": "line 1\n  line 2\n", "tab": "a\tb", "tags": ["!", ""],
+            "log": "a\n\nb\n\n", "size": "1\n2",
+        });
+        let records = vec![("/ports/1", value)];
+        let options = super::FormatterOptions::default().quote(true);
+        let hints: super::Hints = serde_yaml::from_str("units: {size: lines}").unwrap();
+        let output = super::Formatter::new(records.as_slice(), keywords).with_options(options);
+        let output = output.with_hints(|_| Some(&hints));
+        let expected = "/ports/1:\n  \
+                        id \"10\"\n  \
+                        log |+\n    \
+                        a\n\
+                        \n    \
+                        b\n\
+                        \n  \
+                        mode \"no\"\n  \
+                        name \"eth 0\"\n  \
+                        note |\n    \
+                        line 1\n      \
+                        line 2\n  \
+                        path \"/a\"\n  \
+                        plain trunk\n  \
+                        size |- lines\n    \
+                        1\n    \
+                        2\n  \
+                        tab \"a\\tb\"\n  \
+                        tag \"!\"\n  \
+                        tag \"\"\n";
+        assert_eq!(expected, format!("{}", output));
+    }
+
+    #[test]
+    fn test_deep() {
+        let deep: String = (0..40).map(|level| format!("/{}", level)).collect();
+        let records = vec![
+            (deep.clone() + "/a", serde_json::json!({"x": 1})),
+            (deep + "/b", serde_json::json!(null)),
+        ];
+        let output = format!("{}", super::Formatter::new(records.as_slice(), keywords));
+        let expected =
+            format!("{}\n  /a:\n    x 1\n  /b:\n", records[0].0.strip_suffix("/a").unwrap());
+        assert_eq!(expected, output);
+    }
+
+    #[test]
+    fn test_guides() {
+        let records = vec![
+            (
+                "/langs/go",
+                serde_json::json!({"GC": true, "meta": {"year": 2009}, "note": "a\n  b"}),
+            ),
+            ("/langs/go/apps/etcd", serde_json::json!({"kind": "kv"})),
+            ("/langs/rust", serde_json::json!({"GC": false})),
+        ];
+        let options = super::FormatterOptions::default().guides(Some(super::Guides::Unicode));
+        let output = super::Formatter::new(records.as_slice(), keywords).with_options(options);
+        let expected = "/langs\n\
+                        ├── /go:\n\
+                        │   ├── GC yes\n\
+                        │   ├── meta\n\
+                        │   │   └── year 2009\n\
+                        │   ├── note a\n\
+                        │   │     b\n\
+                        │   └── /apps/etcd:\n\
+                        │       └── kind kv\n\
+                        └── /rust:\n    \
+                        └── GC no\n";
+        assert_eq!(expected, format!("{}", output));
+        let options = super::FormatterOptions::default().guides(Some(super::Guides::Ascii));
+        let output = super::Formatter::new(&records[2..], keywords).with_options(options);
+        assert_eq!("/langs/rust:\n`-- GC no\n", format!("{}", output));
+
+        let value = serde_json::json!({"log": "a\n\n  b\n", "rows": [["  x"], ["y"]]});
+        let records = vec![("/ports/1", value)];
+        let options =
+            super::FormatterOptions::default().quote(true).guides(Some(super::Guides::Unicode));
+        let output = super::Formatter::new(records.as_slice(), keywords).with_options(options);
+        let expected = "/ports/1:\n\
+                        ├── log |\n\
+                        │     a\n\
+                        │\n\
+                        │       b\n\
+                        └── rows\n    \
+                        ├── !\n    \
+                        ├── \"  x\"\n    \
+                        ├── !\n    \
+                        ├── y\n    \
+                        └── !\n";
+        assert_eq!(expected, format!("{}", output));
+        let options = super::FormatterOptions::default().guides(Some(super::Guides::Unicode));
+        let output = super::Formatter::new(records.as_slice(), keywords).with_options(options);
+        let expected = "/ports/1:\n\
+                        ├── log a\n\
+                        │\n\
+                        │     b\n\
+                        │\n\
+                        └── rows\n    \
+                        ├── !\n    \
+                        ├──   x\n    \
+                        ├── !\n    \
+                        ├── y\n    \
+                        └── !\n";
+        assert_eq!(expected, format!("{}", output));
+
+        // Text of values is not taken for structure
+        let records = vec![("/x", serde_json::json!({"v": "a\n\u{1e}2\u{1f}b", "w": 1}))];
+        let options = super::FormatterOptions::default().guides(Some(super::Guides::Unicode));
+        let output = super::Formatter::new(records.as_slice(), keywords).with_options(options);
+        assert_eq!("/x:\n├── v a\n│   \u{1e}2\u{1f}b\n└── w 1\n", format!("{}", output));
+    }
+
+    #[test]
+    fn test_stream() {
+        let universe = [
+            "/", "/a", "/a/b", "/a/b/c", "/a/b/d", "/a/e", "/a/e/f", "/b/c", "/b/c/d", "/b/e", "/z",
+        ];
+        for bits in 0..1u32 << universe.len() {
+            let paths = universe.iter().enumerate().filter(|(i, _)| bits & 1 << i != 0);
+            let records: Vec<_> =
+                paths.map(|(i, path)| (*path, serde_json::json!({"i": i}))).collect();
+            let expected = format!("{}", super::Formatter::new(records.as_slice(), keywords));
+            let mut stream = super::StreamFormatter::new(Vec::new(), keywords);
+            for (path, value) in records {
+                stream.push(path.into(), value).unwrap();
+            }
+            assert_eq!(
+                expected,
+                String::from_utf8(stream.finish().unwrap()).unwrap(),
+                "{:b}",
+                bits
+            );
+        }
+
+        let paths = ["/p/2", "/p/2/a", "/p/2/b", "/p/10", "/p/10/a", "/q"];
+        let records: Vec<_> = paths.iter().map(|path| (*path, serde_json::json!({}))).collect();
+        let expected = format!("{}", super::Formatter::new(records.as_slice(), keywords));
+        let mut stream = super::StreamFormatter::new(Vec::new(), keywords);
+        for (path, value) in records {
+            stream.push(path.into(), value).unwrap();
+        }
+        assert_eq!(expected, String::from_utf8(stream.finish().unwrap()).unwrap());
+
+        let mut stream = super::StreamFormatter::new(std::io::sink(), keywords);
+        for i in 0..1000 {
+            stream.push(format!("/items/{:04}", i), serde_json::json!({"i": i})).unwrap();
+            stream.push(format!("/items/{:04}/tags/a", i), serde_json::json!(null)).unwrap();
+            assert!(stream.pending.len() <= 2);
+        }
+
+        let records: Vec<_> =
+            (0..100).map(|i| (format!("/api/ports/{}", i), serde_json::json!({"i": i}))).collect();
+        let expected = format!("{}", super::Formatter::new(records.as_slice(), keywords));
+        let mut stream = super::StreamFormatter::new(Vec::new(), keywords);
+        for (path, value) in records.iter().cloned() {
+            stream.push(path, value).unwrap();
+        }
+        // Held until known that no record outside `/api/ports` follows
+        assert_eq!(100, stream.pending.len());
+        assert_eq!(expected, String::from_utf8(stream.finish().unwrap()).unwrap());
+        let mut stream =
+            super::StreamFormatter::new(Vec::new(), keywords).with_prefix("/api/ports");
+        for (path, value) in records {
+            stream.push(path, value).unwrap();
+            assert!(stream.pending.len() <= 1);
+        }
+        assert_eq!(expected, String::from_utf8(stream.finish().unwrap()).unwrap());
+
+        let universe = ["/a", "/a/b", "/a/b/c", "/a/b/d", "/a/e", "/a/e/f"];
+        for bits in 1..1u32 << universe.len() {
+            let paths = universe.iter().enumerate().filter(|(i, _)| bits & 1 << i != 0);
+            let records: Vec<_> =
+                paths.map(|(i, path)| (*path, serde_json::json!({"i": i}))).collect();
+            let expected = format!("{}", super::Formatter::new(records.as_slice(), keywords));
+            let mut stream = super::StreamFormatter::new(Vec::new(), keywords).with_prefix("/a");
+            for (path, value) in records {
+                stream.push(path.into(), value).unwrap();
+            }
+            let output = String::from_utf8(stream.finish().unwrap()).unwrap();
+            assert_eq!(expected, output, "{:b}", bits);
+        }
+        let mut stream = super::StreamFormatter::new(std::io::sink(), keywords).with_prefix("/a");
+        assert!(stream.push("/ab".into(), serde_json::json!({})).is_err());
+    }
+
+    #[test]
+    fn test_singular() {
+        let words = ["editions", "policies", "addresses", "indices", "vlan_statuses", "ips", "dns"];
+        let singulars: Vec<_> = words.iter().map(|word| super::singular(word)).collect();
+        let expected = ["edition", "policy", "address", "index", "vlan_status", "ip"];
+        let expected: Vec<_> = expected.iter().map(|w| Some(w.to_string())).chain([None]).collect();
+        assert_eq!(expected, singulars);
+        assert_eq!(None, super::singular("status"));
+    }
+
+    #[test]
+    fn test_hints() {
+        let hints: super::Hints = serde_yaml::from_str(
+            "{hide: [_links], order: [size, name], rename: {size: capacity}, units: {size: GB}}",
+        )
+        .unwrap();
+        let value = serde_json::json!({"_links": {}, "name": "disk", "id": 3, "size": 10});
+        let records = vec![("/disks/sda", value.clone()), ("/disks/sdb", value)];
+        let formatter = super::Formatter::new(records.as_slice(), keywords);
+        let output =
+            format!("{}", formatter.with_hints(|path| path.ends_with("sda").then_some(&hints)));
+        let expected = "/disks\n  /sda:\n    capacity 10 GB\n    name disk\n    id 3\n  \
+                        /sdb:\n    _links\n    id 3\n    name disk\n    size 10\n";
+        assert_eq!(expected, output);
+
+        let hints: super::Hints = serde_yaml::from_str("keywords: {tags: label}").unwrap();
+        let records = vec![("/disks/sda", serde_json::json!({"tags": ["ssd"], "ports": [1]}))];
+        let formatter = super::Formatter::new(records.as_slice(), super::singular);
+        let output = format!("{}", formatter.with_hints(|_| Some(&hints)));
+        assert_eq!("/disks/sda:\n  port 1\n  label ssd\n", output);
+    }
 }
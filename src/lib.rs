@@ -1,2 +1,14 @@
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde;
+
+pub mod backend;
+pub mod cli;
+pub mod config;
 pub mod format;
+pub mod mock;
+pub mod output;
 pub mod prefix;
+pub mod table;
+pub mod template;
@@ -1,40 +1,260 @@
 #[macro_use]
 extern crate log;
-#[macro_use]
-extern crate serde;
 
-mod cli;
-mod config;
-mod format;
-mod prefix;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use restcli::backend::{Error, Rest};
+use restcli::format::{FormatterOptions, Guides, Theme};
+use restcli::{backend, cli, config, mock, output};
+
+#[derive(Subcommand, Clone, Debug)]
+enum Command {
+    /// Serve a snapshot as a REST API shaped by the configured APIs
+    ServeMock {
+        /// Snapshot written by `save`
+        snapshot: String,
+        /// Address to listen on
+        #[clap(short, long, default_value = "127.0.0.1:8080")]
+        listen: String,
+    },
+    /// Validate config, reporting errors with their location
+    CheckConfig {
+        /// Also fetch each API of the context, reporting status and shape of responses
+        #[clap(long)]
+        probe: bool,
+    },
+}
 
-use clap::Parser;
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum ColorWhen {
+    /// When stdout is a terminal and NO_COLOR is not set
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum Style {
+    Indent,
+    /// Guides of box-drawing characters
+    Tree,
+    AsciiTree,
+}
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Clone, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
     #[clap(short, long)]
     quiet: bool,
     #[clap(short, action = clap::ArgAction::Count)]
     verbosity: u8,
-    /// Specify config path
-    #[clap(short = 'f', long, default_value = "/etc/restcli/config.yaml")]
-    config_path: String,
+    /// Specify config path, otherwise looked up from $RESTCLI_CONFIG, ./.restcli.yaml,
+    /// $XDG_CONFIG_HOME/restcli/config.yaml and /etc/restcli/config.yaml
+    #[clap(short = 'f', long)]
+    config_path: Option<String>,
+    /// Override server URL of the context
+    #[clap(long)]
+    url: Option<String>,
+    /// Send header with every request, overriding configured ones
+    #[clap(short = 'H', long = "header", value_name = "NAME: VALUE", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+    /// Context to start with, instead of `current-context` of config
+    #[clap(short, long)]
+    context: Option<String>,
+    /// Browse a snapshot written by `save` instead of querying the server
+    #[clap(long, value_name = "FILE")]
+    offline: Option<String>,
+    /// Output of `list`: notation, json, json-flat, yaml, yaml-flat, csv or table
+    #[clap(short, long, default_value = "notation")]
+    output: output::Output,
+    /// Colorize output
+    #[clap(long, value_enum, default_value = "auto")]
+    color: ColorWhen,
+    /// Quote ambiguous strings and print multi-line strings as blocks
+    #[clap(long)]
+    quote: bool,
+    /// Layout of notation
+    #[clap(long, value_enum, default_value = "indent")]
+    style: Style,
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+fn parse_header(text: &str) -> Result<(String, String), String> {
+    match text.split_once(':') {
+        Some((name, value)) => Ok((name.trim().into(), value.trim().into())),
+        None => Err(format!("Header {} is not of form NAME: VALUE", text)),
+    }
+}
+
+const INSECURE_BANNER: &str = "\
+****************************************************************
+* WARNING: TLS certificate verification is disabled, anyone on *
+* the network path may intercept or tamper with the traffic    *
+****************************************************************";
+
+/// Contexts of config, loaded again as specified by arguments on reload
+struct Contexts(config::Config, Args);
+
+impl cli::Connector<Rest> for Contexts {
+    fn names(&self) -> Vec<String> {
+        self.0.context_names().into_iter().map(Into::into).collect()
+    }
+
+    fn connect(&self, name: &str) -> backend::Result<(Rest, Vec<config::API>)> {
+        let server = self.0.server(name).map_err(Error::Config)?;
+        if server.tls.map(|tls| tls.insecure_skip_verify).unwrap_or_default() {
+            eprintln!("{}", INSECURE_BANNER);
+        }
+        let rest = Rest::new(server.url.to_owned(), server.auth, server.tls)?;
+        Ok((rest.with_headers(&self.1.headers)?, server.apis.to_vec()))
+    }
+
+    fn keywords(&self) -> BTreeMap<String, String> {
+        self.0.keywords.clone()
+    }
+
+    fn reload(&self, context: &str) -> backend::Result<Box<dyn cli::Connector<Rest>>> {
+        let config = load_config(&self.1, Some(context)).map_err(Error::Config)?;
+        Ok(Box::new(Contexts(config, self.1.clone())))
+    }
+}
+
+fn config_path(args: &Args) -> Option<PathBuf> {
+    if let Some(path) = args.config_path.as_ref() {
+        return Some(path.into());
+    }
+    let candidates = config::candidates(|name| std::env::var(name).ok());
+    candidates.into_iter().find(|path| path.exists())
 }
 
-fn load_config(path: &str) -> Result<config::Config, String> {
-    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
-    serde_yaml::from_reader(file).map_err(|e| e.to_string())
+/// Load config, with `--url` overriding the given context, or the one to start with if none
+fn load_config(args: &Args, context: Option<&str>) -> Result<config::Config, String> {
+    let mut config = match (config_path(args), &args.url) {
+        (Some(path), _) => {
+            let (config, unknown) =
+                config::load(&path).map_err(|e| format!("Load config fail: {}", e))?;
+            for (location, key) in unknown {
+                warn!("Unknown key {} in config {}", key, location);
+            }
+            config
+        }
+        (None, Some(_)) => {
+            let apis = vec![config::API { path: "/".into(), ..Default::default() }];
+            config::Config { apis, ..Default::default() }
+        }
+        (None, None) => return Err("No config found, specify one with -f or --url".into()),
+    };
+    let context = context.or(args.context.as_deref()).unwrap_or(config.current_context());
+    let context = context.to_owned();
+    config.override_with(&context, args.url.as_deref());
+    Ok(config)
 }
 
-fn run(config_path: &str) -> Result<(), String> {
-    match load_config(config_path) {
-        Ok(config) => {
-            cli::CLI::new(config.url, config.apis).map_err(|e| format!("{}", e))?.run();
-            Ok(())
+fn check_config(args: &Args, probe: bool) -> Result<(), String> {
+    let path = config_path(args).ok_or("No config found")?;
+    let (config, unknown) = match config::load(&path) {
+        Ok(loaded) => loaded,
+        Err(error) => {
+            println!("{}: error: {}", error.file, error.message);
+            return Err(format!("Config {} is invalid", path.display()));
         }
-        Err(error) => Err(format!("Load config {} fail: {}", config_path, error)),
+    };
+    for (location, key) in unknown.iter() {
+        match config::hint(key) {
+            Some(known) => {
+                println!("{}: warning: unknown key {}, did you mean {}?", location, key, known)
+            }
+            None => println!("{}: warning: unknown key {}", location, key),
+        }
+    }
+    for (key, warning) in config.warnings().iter() {
+        let location = config.locate(key).map(|location| location.to_string());
+        let location = location.unwrap_or_else(|| path.display().to_string());
+        println!("{}: warning: {}: {}", location, key, warning);
+    }
+    let errors = config.validate();
+    for (key, error) in errors.iter() {
+        let location = config.locate(key).map(|location| location.to_string());
+        let location = location.unwrap_or_else(|| path.display().to_string());
+        println!("{}: error: {}: {}", location, key, error);
     }
+    if !errors.is_empty() {
+        return Err(format!("Config {} is invalid", path.display()));
+    }
+    if !probe {
+        return Ok(());
+    }
+    let config = load_config(args, None)?;
+    let context = args.context.clone().unwrap_or(config.current_context().into());
+    let (rest, apis) = cli::Connector::connect(&Contexts(config, args.clone()), &context)
+        .map_err(|e| format!("Connect to context {} fail: {}", context, e))?;
+    let mut failed = false;
+    for probe in cli::probe(&rest, &apis) {
+        let status = probe.status.map(|status| format!(" {}", status)).unwrap_or_default();
+        match &probe.result {
+            Ok(shape) => println!("{}: ok{}, {}", probe.url, status, shape),
+            Err(error) => println!("{}: failed{}: {}", probe.url, status, error),
+        }
+        failed |= probe.result.is_err();
+    }
+    match failed {
+        true => Err(format!("Probe context {} fail", context)),
+        false => Ok(()),
+    }
+}
+
+fn load_snapshot(path: &str) -> Result<backend::Memory, String> {
+    let file =
+        std::fs::File::open(path).map_err(|e| format!("Open snapshot {} fail: {}", path, e))?;
+    backend::Memory::from_reader(file).map_err(|e| format!("Load snapshot {} fail: {}", path, e))
+}
+
+fn serve_mock(args: &Args, snapshot: &str, listen: &str) -> Result<(), String> {
+    let config = load_config(args, None)?;
+    let context = args.context.as_deref().unwrap_or(config.current_context());
+    let apis = config.server(context)?.apis.to_vec();
+    let mock = mock::Mock::new(apis, load_snapshot(snapshot)?);
+    mock.serve(listen).map_err(|e| format!("Serve mock on {} fail: {}", listen, e))
+}
+
+fn run_offline(args: &Args, path: &str) -> Result<(), String> {
+    let memory = load_snapshot(path)?;
+    let apis = vec![config::API { path: "/".into(), ..Default::default() }];
+    let cli = cli::CLI::new(memory, apis).map_err(|e| format!("{}", e))?;
+    let options = formatter_options(args, Theme::default());
+    cli.with_output(args.output.clone()).with_formatter_options(options).run();
+    Ok(())
+}
+
+fn formatter_options(args: &Args, theme: Theme) -> FormatterOptions {
+    let colored = match args.color {
+        ColorWhen::Always => true,
+        ColorWhen::Never => false,
+        ColorWhen::Auto => {
+            let no_color = std::env::var_os("NO_COLOR").filter(|value| !value.is_empty());
+            termion::is_tty(&std::io::stdout()) && no_color.is_none()
+        }
+    };
+    let guides = match args.style {
+        Style::Indent => None,
+        Style::Tree => Some(Guides::Unicode),
+        Style::AsciiTree => Some(Guides::Ascii),
+    };
+    let options = FormatterOptions::default().theme(colored.then_some(theme));
+    options.quote(args.quote).guides(guides)
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let config = load_config(args, None)?;
+    let options = formatter_options(args, config.theme()?);
+    let context = args.context.clone().unwrap_or(config.current_context().into());
+    let connector = Box::new(Contexts(config, args.clone()));
+    let cli = cli::CLI::with_connector(connector, &context).map_err(|e| format!("{}", e))?;
+    cli.with_output(args.output.clone()).with_formatter_options(options).run();
+    Ok(())
 }
 
 fn main() {
@@ -47,7 +267,13 @@ fn main() {
     };
     log::set_max_level(level);
     env_logger::builder().filter(Some("restcli"), level).target(env_logger::Target::Stdout).init();
-    if let Some(err) = run(&args.config_path).err() {
+    let result = match (&args.command, &args.offline) {
+        (Some(Command::ServeMock { snapshot, listen }), _) => serve_mock(&args, snapshot, listen),
+        (Some(Command::CheckConfig { probe }), _) => check_config(&args, *probe),
+        (None, Some(path)) => run_offline(&args, path),
+        (None, None) => run(&args),
+    };
+    if let Some(err) = result.err() {
         error!("{}", err);
         std::process::exit(1)
     }
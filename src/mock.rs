@@ -0,0 +1,233 @@
+use std::io;
+
+use serde_json::{json, Map, Value};
+use tiny_http::{Header, Response, Server};
+
+use crate::backend::{Backend, Memory};
+use crate::config::API;
+use crate::template::{self, Vars};
+
+#[derive(Debug, PartialEq)]
+enum Route {
+    /// Collection listed under prefix, nested if sub APIs own deeper records
+    Collection(String, bool),
+    Entity(String),
+}
+
+/// Serves a snapshot as a REST API shaped by an API tree
+///
+/// A collection lists records under its display prefix, keyed by relative path. When the API
+/// has sub APIs, records below another record are left to them. Any other path addresses a
+/// record by its display path. Jsonpath of APIs is not reverted, so the responses are always
+/// plain objects.
+pub struct Mock {
+    apis: Vec<API>,
+    backend: Memory,
+}
+
+impl Mock {
+    pub fn new(apis: Vec<API>, backend: Memory) -> Self {
+        Self { apis, backend }
+    }
+
+    fn has_record_between(&self, prefix: &str, path: &str) -> bool {
+        let relative = &path[prefix.len()..];
+        let mut indices = relative.match_indices('/').map(|(index, _)| prefix.len() + index);
+        indices.any(|index| self.backend.records().contains_key(&path[..index]))
+    }
+
+    fn collection(&self, prefix: &str, nested: bool) -> Map<String, Value> {
+        let records = self.backend.records().range(prefix.to_owned()..);
+        let records = records.take_while(|(path, _)| path.starts_with(prefix));
+        let records = records.filter(|(path, _)| path.len() > prefix.len());
+        let records = records.filter(|(path, _)| !nested || !self.has_record_between(prefix, path));
+        records.map(|(path, value)| (path[prefix.len()..].to_owned(), value.clone())).collect()
+    }
+
+    /// Entity at display path `prefix` and URL `base`
+    fn route_apis(
+        &self,
+        apis: &[API],
+        prefix: &str,
+        base: &str,
+        vars: &Vars,
+        url: &str,
+    ) -> Option<Route> {
+        for api in apis {
+            let sub_apis = api.apis.as_deref().unwrap_or_default();
+            let path = match template::is_template(&api.path) {
+                true => template::render(&api.path, vars).ok()?,
+                false => base.to_owned() + api.path.trim_start_matches('/'),
+            };
+            let (path, query) = path.split_once('?').unwrap_or((&path, ""));
+            let prefix = match api.display_path() {
+                Some(display) => match template::render(display, vars).ok()?.trim_matches('/') {
+                    "" => "/".into(),
+                    display => format!("/{}/", display),
+                },
+                None => prefix.to_owned(),
+            };
+            let (url_path, url_query) = url.split_once('?').unwrap_or((url, ""));
+            let matched = query
+                .split('&')
+                .filter(|p| !p.is_empty())
+                .all(|pair| url_query.split('&').any(|p| p == pair));
+            if url_path.trim_end_matches('/') == path.trim_end_matches('/') && matched {
+                return Some(Route::Collection(prefix, !sub_apis.is_empty()));
+            }
+            if sub_apis.is_empty() {
+                continue;
+            }
+            for (key, value) in self.collection(&prefix, true) {
+                let mut vars = vars.clone();
+                if let Some(name) = api.var.as_ref() {
+                    template::bind(&mut vars, name, &key, &value);
+                }
+                let (prefix, base) = (format!("{}{}/", prefix, key), format!("{}{}/", base, key));
+                if let Some(route) = self.route_apis(sub_apis, &prefix, &base, &vars, url) {
+                    return Some(route);
+                }
+            }
+        }
+        None
+    }
+
+    fn route(&self, url: &str) -> Route {
+        match self.route_apis(&self.apis, "/", "/", &Vars::new(), url) {
+            Some(route) => route,
+            None => Route::Entity(url.split_once('?').map(|(path, _)| path).unwrap_or(url).into()),
+        }
+    }
+
+    fn handle(&mut self, method: &str, url: &str, body: &str) -> (u16, Value) {
+        let path = match self.route(url) {
+            Route::Collection(prefix, nested) => match method {
+                "GET" => return (200, Value::Object(self.collection(&prefix, nested))),
+                _ => return (405, json!({"error": "Method not allowed"})),
+            },
+            Route::Entity(path) => path,
+        };
+        let value = match method {
+            "PUT" | "PATCH" => match serde_json::from_str(body) {
+                Ok(value) => value,
+                Err(error) => return (400, json!({"error": error.to_string()})),
+            },
+            _ => Value::Null,
+        };
+        let result = match method {
+            "GET" => self.backend.get(&path),
+            "PUT" => self.backend.put(&path, value).and_then(|_| self.backend.get(&path)),
+            "PATCH" => self.backend.patch(&path, value).and_then(|_| self.backend.get(&path)),
+            "DELETE" => self.backend.delete(&path).map(|_| Value::Null),
+            _ => return (405, json!({"error": "Method not allowed"})),
+        };
+        match result {
+            Ok(value) => (200, value),
+            Err(error) => (404, json!({"error": error.to_string()})),
+        }
+    }
+
+    fn run(mut self, server: Server) -> io::Result<()> {
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+        for mut request in server.incoming_requests() {
+            let mut body = String::new();
+            let method = request.method().as_str().to_owned();
+            let (status, value) = match request.as_reader().read_to_string(&mut body) {
+                Ok(_) => self.handle(&method, request.url(), &body),
+                Err(error) => (400, json!({"error": error.to_string()})),
+            };
+            debug!("{} {} {}", method, request.url(), status);
+            let response = Response::from_string(value.to_string()).with_status_code(status);
+            if let Err(error) = request.respond(response.with_header(content_type.clone())) {
+                warn!("Respond fail: {}", error);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn serve(self, address: &str) -> io::Result<()> {
+        let server = Server::http(address).map_err(io::Error::other)?;
+        info!("Serving mock on {}", address);
+        self.run(server)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    use serde_json::{json, Value};
+
+    use super::{Mock, Route};
+    use crate::backend::test::serve;
+    use crate::backend::{Backend, Memory, Rest};
+    use crate::config::API;
+
+    fn sample() -> Mock {
+        let test_data = include_str!("../test/sample-data.yaml");
+        let memory = Memory::from_reader(test_data.as_bytes()).unwrap();
+        let applications = API { path: "/applications".into(), ..Default::default() };
+        let languages =
+            API { path: "/languages".into(), apis: Some(vec![applications]), ..Default::default() };
+        Mock::new(vec![languages], memory)
+    }
+
+    #[test]
+    fn test_route() {
+        let mock = sample();
+        assert_eq!(Route::Collection("/".into(), true), mock.route("/languages"));
+        let expected = Route::Collection("/languages/go/".into(), false);
+        assert_eq!(expected, mock.route("/languages/go/applications?expand=true"));
+        assert_eq!(Route::Entity("/languages/go".into()), mock.route("/languages/go"));
+    }
+
+    #[test]
+    fn test_serve() {
+        let mut mock = sample();
+        let (_, value) = mock.handle("PATCH", "/languages/go", r#"{"typed": true}"#);
+        assert_eq!(json!({"GC": "yes", "typed": true}), value);
+
+        let (url, _) = serve(move |server| mock.run(server));
+        let rest = Rest::new(url.clone(), None, None).unwrap();
+        let languages = rest.list("/languages", &Default::default()).unwrap().value;
+        let keys: Vec<&String> = languages.as_object().unwrap().keys().collect();
+        assert_eq!(vec!["languages/C%2FC++", "languages/go", "languages/rust"], keys);
+        let applications =
+            rest.list("/languages/go/applications", &Default::default()).unwrap().value;
+        let expected = json!({"applications/etcd": {"category": "database"}});
+        assert_eq!(Some(&expected["applications/etcd"]), applications.get("applications/etcd"));
+        assert_eq!(Value::Bool(true), rest.get("/languages/go").unwrap()["typed"]);
+        assert!(rest.get("/languages/java").is_err());
+        // Body not in UTF-8 is answered, keeping server up
+        let address = url.trim_start_matches("http://").trim_end_matches('/');
+        let mut stream = TcpStream::connect(address).unwrap();
+        let request =
+            b"PUT /languages/go HTTP/1.1\r\nConnection: close\r\nContent-Length: 2\r\n\r\n\xff\xfe";
+        stream.write_all(request).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+        assert_eq!(Value::Bool(true), rest.get("/languages/go").unwrap()["typed"]);
+        let apis = vec![API { path: "/languages".into(), ..Default::default() }];
+        assert_eq!(Some(200), crate::cli::probe(&rest, &apis)[0].status);
+        let apis = vec![API { path: "/languages/java".into(), ..Default::default() }];
+        assert_eq!(Some(404), crate::cli::probe(&rest, &apis)[0].status);
+    }
+
+    #[test]
+    fn test_route_template() {
+        let yaml = "
+            - path: /v2/langs
+              display: /languages
+              var: lang
+              apis:
+                - path: /v2/apps?lang={lang}&gc={lang.GC}";
+        let apis: Vec<API> = serde_yaml::from_str(yaml).unwrap();
+        let test_data = include_str!("../test/sample-data.yaml");
+        let mut mock = Mock::new(apis, Memory::from_reader(test_data.as_bytes()).unwrap());
+        assert_eq!(Route::Collection("/languages/".into(), true), mock.route("/v2/langs"));
+        let (_, value) = mock.handle("GET", "/v2/apps?lang=rust&gc=no", "");
+        assert_eq!(json!({"applications/restcli": {"category": "ultility"}}), value);
+    }
+}
@@ -0,0 +1,195 @@
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+use urlencoding::decode;
+
+use crate::table::{cell, Table};
+
+/// How `list` prints records
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Output {
+    /// Indented notation of `Formatter`
+    #[default]
+    Notation,
+    /// Nested by path segments
+    Json,
+    /// Object of path to value
+    JsonFlat,
+    Yaml,
+    YamlFlat,
+    /// One row per record, columns from union of fields
+    Csv,
+    /// Aligned table of entities below current path
+    Table(Table),
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (name, args) = text.trim().split_once(' ').unwrap_or((text.trim(), ""));
+        if name == "table" {
+            return Ok(Self::Table(args.parse()?));
+        }
+        if !args.trim().is_empty() {
+            return Err(format!("Unexpected argument {}", args));
+        }
+        match name {
+            "notation" => Ok(Self::Notation),
+            "json" => Ok(Self::Json),
+            "json-flat" => Ok(Self::JsonFlat),
+            "yaml" => Ok(Self::Yaml),
+            "yaml-flat" => Ok(Self::YamlFlat),
+            "csv" => Ok(Self::Csv),
+            name => Err(format!("Unknown output {}, expect one of {}", name, NAMES.join(", "))),
+        }
+    }
+}
+
+const NAMES: &[&str] = &["notation", "json", "json-flat", "yaml", "yaml-flat", "csv", "table"];
+
+/// Value placed where records of child paths are also placed, when not an object
+const VALUE_KEY: &str = "";
+
+/// Child object of node, wrapping value of node under `VALUE_KEY` if not an object
+fn descend(node: &mut Map<String, Value>, segment: String) -> &mut Map<String, Value> {
+    let child = node.entry(segment).or_insert_with(|| Value::Object(Map::new()));
+    if !child.is_object() {
+        let value = std::mem::take(child);
+        *child = Value::Object(Map::from_iter([(VALUE_KEY.into(), value)]));
+    }
+    child.as_object_mut().unwrap()
+}
+
+/// Records nested by path segments, fields of a record merged with its children
+///
+/// A field named as a child segment is dropped with a warning, the key taken by the child
+fn nest<S: AsRef<str>>(records: &[(S, Value)]) -> Value {
+    let mut root = Map::new();
+    // Deeper records first, so that children are all in place when fields merged
+    let mut records: Vec<&(S, Value)> = records.iter().collect();
+    records.sort_by_key(|(path, _)| Reverse(path.as_ref().matches('/').count()));
+    for (path, value) in records {
+        let segments = path.as_ref().split('/').filter(|segment| !segment.is_empty());
+        let mut segments: Vec<String> =
+            segments.map(|s| decode(s).map(|s| s.into_owned()).unwrap_or(s.into())).collect();
+        let mut node = &mut root;
+        let last = match segments.pop() {
+            Some(last) => last,
+            None => VALUE_KEY.into(),
+        };
+        for segment in segments {
+            node = descend(node, segment);
+        }
+        match (node.get_mut(&last), value) {
+            (Some(Value::Object(node)), Value::Object(fields)) => {
+                for (field, value) in fields {
+                    if node.contains_key(field) {
+                        warn!("Field {} of {} dropped, collides with child", field, path.as_ref());
+                        continue;
+                    }
+                    node.insert(field.clone(), value.clone());
+                }
+            }
+            (Some(Value::Object(node)), value) => {
+                node.insert(VALUE_KEY.into(), value.clone());
+            }
+            (_, value) => {
+                node.insert(last, value.clone());
+            }
+        }
+    }
+    Value::Object(root)
+}
+
+fn flatten<S: AsRef<str>>(records: &[(S, Value)]) -> Value {
+    Value::Object(
+        records.iter().map(|(path, value)| (path.as_ref().into(), value.clone())).collect(),
+    )
+}
+
+fn csv<S: AsRef<str>>(records: &[(S, Value)]) -> Result<String, String> {
+    let mut columns = BTreeSet::new();
+    for (_, value) in records {
+        columns.extend(value.as_object().into_iter().flat_map(|fields| fields.keys()));
+    }
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let header = std::iter::once("path").chain(columns.iter().map(|c| c.as_str()));
+    writer.write_record(header).map_err(|e| e.to_string())?;
+    for (path, value) in records {
+        let cells = columns.iter().map(|column| cell(value.get(column.as_str())));
+        let row = std::iter::once(path.as_ref().to_owned()).chain(cells);
+        writer.write_record(row).map_err(|e| e.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+impl Output {
+    /// Render records in this output, except notation which needs `Formatter` and table
+    pub fn render<S: AsRef<str>>(&self, records: &[(S, Value)]) -> Result<String, String> {
+        let json = |value| match serde_json::to_string_pretty(&value) {
+            Ok(text) => Ok(text + "\n"),
+            Err(error) => Err(error.to_string()),
+        };
+        let yaml = |value| serde_yaml::to_string(&value).map_err(|e| e.to_string());
+        match self {
+            Self::Notation | Self::Table(_) => Err("Not a serialization output".into()),
+            Self::Json => json(nest(records)),
+            Self::JsonFlat => json(flatten(records)),
+            Self::Yaml => yaml(nest(records)),
+            Self::YamlFlat => yaml(flatten(records)),
+            Self::Csv => csv(records),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{nest, Output};
+
+    #[test]
+    fn test_render() {
+        let records = vec![
+            ("/languages/C%2FC++", json!({"GC": "no"})),
+            ("/languages/go", json!({"GC": "yes", "tags": ["cloud", "k8s"]})),
+            ("/languages/go/applications/etcd", json!({"category": "data, \"kv\""})),
+            ("/languages/go/ports", json!(2)),
+            ("/languages/go/ports/1", json!(80)),
+        ];
+        let nested: serde_json::Value =
+            serde_json::from_str(&Output::Json.render(&records).unwrap()).unwrap();
+        let expected = json!({"languages": {
+            "C/C++": {"GC": "no"},
+            "go": {
+                "GC": "yes",
+                "tags": ["cloud", "k8s"],
+                "applications": {"etcd": {"category": "data, \"kv\""}},
+                "ports": {"": 2, "1": 80},
+            },
+        }});
+        assert_eq!(expected, nested);
+        let flat = Output::YamlFlat.render(&records[..1]).unwrap();
+        assert_eq!("/languages/C%2FC++:\n  GC: no\n", flat);
+        let expected = "path,GC,category,tags\n\
+                        /languages/C%2FC++,no,,\n\
+                        /languages/go,yes,,\"[\"\"cloud\"\",\"\"k8s\"\"]\"\n\
+                        /languages/go/applications/etcd,,\"data, \"\"kv\"\"\",\n";
+        assert_eq!(expected, Output::Csv.render(&records[..3]).unwrap());
+        assert!("xml".parse::<Output>().is_err() && "json GC".parse::<Output>().is_err());
+        assert!(matches!("table GC sort=GC".parse(), Ok(Output::Table(_))));
+    }
+
+    #[test]
+    fn test_nest_collision() {
+        let mut records = vec![("/a", json!({"b": 1, "c": 2})), ("/a/b", json!({"x": 3}))];
+        let expected = json!({"a": {"b": {"x": 3}, "c": 2}});
+        assert_eq!(expected, nest(&records));
+        records.reverse();
+        assert_eq!(expected, nest(&records));
+    }
+}
@@ -1,4 +1,7 @@
-pub const MAX_LEVEL: usize = 16;
+use smallvec::SmallVec;
+
+/// Levels of a path kept inline, deeper ones spill to heap
+pub const INLINE_LEVELS: usize = 16;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Prefix<'a> {
@@ -15,7 +18,7 @@ impl<'a> From<&(&'a str, core::ops::Range<usize>)> for Prefix<'a> {
 
 struct Path<'a> {
     pub raw: &'a str,
-    pub tokens: heapless::Vec<&'a str, MAX_LEVEL>,
+    pub tokens: SmallVec<[&'a str; INLINE_LEVELS]>,
 }
 
 impl<'a> From<&'a str> for Path<'a> {
@@ -47,7 +50,10 @@ impl<'a> Path<'a> {
 }
 
 impl<'a> Prefix<'a> {
-    /// paths should be lexical ordered
+    /// paths should be lexical ordered, or any order keeping paths below a path right after it
+    ///
+    /// Only segments shared by consecutive paths are compared, so a pre-order of segments with
+    /// siblings sorted otherwise, as records are displayed, gives the same prefixes
     pub fn build<T: DoubleEndedIterator<Item = &'a str>>(paths: T) -> Vec<Prefix<'a>> {
         let mut retval = Vec::new();
         let mut iter = paths.rev().enumerate();
@@ -56,8 +62,8 @@ impl<'a> Prefix<'a> {
             None => return Vec::with_capacity(0),
         };
         let mut sum = 1;
-        let mut ref_counts = heapless::Vec::<usize, MAX_LEVEL>::new();
-        ref_counts.resize(ref_path.num_level(), 1).ok();
+        let mut ref_counts = SmallVec::<[usize; INLINE_LEVELS]>::new();
+        ref_counts.resize(ref_path.num_level(), 1);
         for (index, raw_path) in iter {
             let path: Path = raw_path.into();
             let num_match = ref_path.match_tokens(&path);
@@ -70,15 +76,16 @@ impl<'a> Prefix<'a> {
                     let start = index - ref_counts[i];
                     retval.push(Prefix { text: ref_path.pop(length), range: start..index });
                     length = 1;
-                } else if ref_counts[i] > 1 && ref_counts[i] == ref_counts[i - 1] {
+                } else if ref_counts[i] == ref_counts[i - 1] {
                     length += 1;
                 } else {
-                    ref_path.pop(1);
+                    ref_path.pop(length);
+                    length = 1;
                 }
             }
             ref_counts.truncate(ref_path.num_level());
             if path.num_level() > ref_path.num_level() {
-                ref_counts.resize(path.num_level(), 1).ok();
+                ref_counts.resize(path.num_level(), 1);
                 ref_path = path;
             }
             sum += 1;
@@ -92,12 +99,13 @@ impl<'a> Prefix<'a> {
             } else if ref_counts[i] == ref_counts[i - 1] {
                 length += 1;
             } else {
-                ref_path.pop(1);
+                ref_path.pop(length);
+                length = 1;
             }
         }
         retval.reverse();
-        for i in 0..retval.len() {
-            let range = &mut retval[i].range;
+        for prefix in retval.iter_mut() {
+            let range = &mut prefix.range;
             *range = (sum - range.end)..(sum - range.start);
         }
         retval
@@ -115,6 +123,31 @@ mod test {
         let expected = [("/a", 0..1), ("/bc", 1..2), ("/def", 2..3), ("/g", 3..4)];
         let expected: Vec<Prefix<'_>> = expected.iter().map(Into::into).collect();
         assert_eq!(expected, prefixes);
+
+        let prefixes = Prefix::build(["/a/b/c/d", "/a/e", "/x/y"].into_iter());
+        let expected: Vec<Prefix<'_>> =
+            [("/a", 0..2), ("/x/y", 2..3)].iter().map(Into::into).collect();
+        assert_eq!(expected, prefixes);
+    }
+
+    #[test]
+    fn test_build_prefix_preorder() {
+        let paths = ["/p/2", "/p/2/a", "/p/2/b", "/p/10", "/p/10/a", "/q"];
+        let prefixes = Prefix::build(paths.into_iter());
+        let expected = [("/p", 0..5), ("/2", 0..3), ("/10", 3..5), ("/q", 5..6)];
+        let expected: Vec<Prefix<'_>> = expected.iter().map(Into::into).collect();
+        assert_eq!(expected, prefixes);
+    }
+
+    #[test]
+    fn test_build_prefix_deep() {
+        let levels: Vec<String> = (0..40).map(|level| format!("/{}", level)).collect();
+        let (top, deep) = (levels[..20].concat(), levels.concat());
+        let (a, b) = (deep.clone() + "/a", deep.clone() + "/b");
+        let prefixes = Prefix::build([top.as_str(), a.as_str(), b.as_str()].into_iter());
+        let expected = [(top.as_str(), 0..3), (&deep[top.len()..], 1..3)];
+        let expected: Vec<Prefix<'_>> = expected.iter().map(Into::into).collect();
+        assert_eq!(expected, prefixes);
     }
 
     #[test]
@@ -0,0 +1,200 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use serde_json::Value;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use urlencoding::decode;
+
+const KEY_COLUMN: &str = "key";
+const GAP: &str = "  ";
+const ELLIPSIS: char = '…';
+/// Width that columns are not truncated below
+const MIN_WIDTH: usize = 3;
+
+/// Aligned table of entities, one row each, key as first column
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Table {
+    /// Columns after key, union of fields if empty
+    pub columns: Vec<String>,
+    /// Column sorted by and whether descending, by key if absent
+    pub sort: Option<(String, bool)>,
+}
+
+/// Parse `[COLUMN,...] [sort=[-]COLUMN]`
+impl FromStr for Table {
+    type Err = String;
+
+    fn from_str(args: &str) -> Result<Self, Self::Err> {
+        let mut table = Self::default();
+        for arg in args.split_whitespace() {
+            match arg.strip_prefix("sort=") {
+                Some(column) => match column.strip_prefix('-') {
+                    Some(column) => table.sort = Some((column.into(), true)),
+                    None => table.sort = Some((column.into(), false)),
+                },
+                None if table.columns.is_empty() => {
+                    table.columns = arg.split(',').map(Into::into).collect();
+                }
+                None => return Err(format!("Unexpected argument {}", arg)),
+            }
+        }
+        Ok(table)
+    }
+}
+
+/// Text of a field in a cell, empty if absent or null
+pub(crate) fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(string)) => string.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+fn compare(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a, b) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => {
+            let (a, b) = (a.as_f64().unwrap_or_default(), b.as_f64().unwrap_or_default());
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (a, b) => cell(a).cmp(&cell(b)),
+    }
+}
+
+/// Text cut to display width, ending with ellipsis if cut
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_owned();
+    }
+    let mut output = String::new();
+    let mut used = 0;
+    for ch in text.chars() {
+        let char_width = ch.width().unwrap_or_default();
+        if used + char_width + 1 > width {
+            break;
+        }
+        output.push(ch);
+        used += char_width;
+    }
+    output.push(ELLIPSIS);
+    output
+}
+
+/// Narrow widest columns until total width fits
+fn fit(widths: &mut [usize], max_width: usize) {
+    let gaps = GAP.len() * widths.len().saturating_sub(1);
+    while widths.iter().sum::<usize>() + gaps > max_width {
+        match widths.iter_mut().filter(|width| **width > MIN_WIDTH).max() {
+            Some(widest) => *widest -= 1,
+            None => return,
+        }
+    }
+}
+
+/// Entities directly below base keyed relative to it, or all records below if none
+pub fn rows<'a, S: AsRef<str>>(records: &'a [(S, Value)], base: &str) -> Vec<(&'a str, &'a Value)> {
+    let relative = records.iter().filter_map(|(path, value)| {
+        let key = path.as_ref().strip_prefix(base.trim_end_matches('/'))?.strip_prefix('/')?;
+        (!key.is_empty()).then_some((key, value))
+    });
+    let (direct, nested): (Vec<_>, Vec<_>) = relative.partition(|(key, _)| !key.contains('/'));
+    match direct.is_empty() {
+        true => nested,
+        false => direct,
+    }
+}
+
+impl Table {
+    /// Render rows of key and entity, fitting in width if specified
+    pub fn render(&self, rows: &[(&str, &Value)], max_width: Option<usize>) -> String {
+        let mut columns = self.columns.clone();
+        if columns.is_empty() {
+            for (_, value) in rows {
+                let fields = value.as_object().into_iter().flat_map(|fields| fields.keys());
+                columns.extend(fields.cloned());
+            }
+            columns.sort();
+            columns.dedup();
+        }
+        let mut rows = rows.to_vec();
+        if let Some((column, descending)) = self.sort.as_ref() {
+            rows.sort_by(|(_, a), (_, b)| {
+                let ordering = compare(a.get(column), b.get(column));
+                match descending {
+                    true => ordering.reverse(),
+                    false => ordering,
+                }
+            });
+        }
+        let header = std::iter::once(KEY_COLUMN.to_owned()).chain(columns.iter().cloned());
+        let mut lines = vec![header.collect::<Vec<_>>()];
+        for (key, value) in rows {
+            let cells = columns.iter().map(|column| cell(value.get(column)));
+            let key = decode(key).map(|key| key.into_owned()).unwrap_or(key.into());
+            lines.push(std::iter::once(key).chain(cells).collect());
+        }
+        let mut widths = vec![0; columns.len() + 1];
+        for line in lines.iter() {
+            for (width, cell) in widths.iter_mut().zip(line.iter()) {
+                *width = (*width).max(cell.width());
+            }
+        }
+        if let Some(max_width) = max_width {
+            fit(&mut widths, max_width);
+        }
+        let mut output = String::new();
+        for line in lines {
+            let cells = line.iter().zip(widths.iter()).map(|(cell, &width)| {
+                let cell = truncate(cell, width);
+                let padding = width - cell.width();
+                cell + &" ".repeat(padding)
+            });
+            output += cells.collect::<Vec<_>>().join(GAP).trim_end();
+            output.push('\n');
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::Table;
+
+    #[test]
+    fn test_render() {
+        let (go, rust, cpp) = (
+            json!({"GC": "yes", "year": 2009}),
+            json!({"GC": "no", "year": 2015, "mascot": "Ferris 🦀"}),
+            json!({"GC": "no", "year": 1985}),
+        );
+        let rows = vec![("C%2FC++", &cpp), ("go", &go), ("rust", &rust)];
+        let table: Table = "".parse().unwrap();
+        let expected = "key    GC   mascot     year\n\
+                        C/C++  no              1985\n\
+                        go     yes             2009\n\
+                        rust   no   Ferris 🦀  2015\n";
+        assert_eq!(expected, table.render(&rows, None));
+
+        let table: Table = "year,mascot sort=-year".parse().unwrap();
+        let expected = "key    year  mas…\n\
+                        rust   2015  Fer…\n\
+                        go     2009\n\
+                        C/C++  1985\n";
+        assert_eq!(expected, table.render(&rows, Some(17)));
+        assert!("GC year".parse::<Table>().is_err());
+
+        let records = vec![("/langs/go", go.clone()), ("/langs/go/apps/etcd", json!({}))];
+        let keys: Vec<&str> = super::rows(&records, "/langs/").iter().map(|row| row.0).collect();
+        assert_eq!(vec!["go"], keys);
+        let keys: Vec<&str> = super::rows(&records, "/langs/go").iter().map(|row| row.0).collect();
+        assert_eq!(vec!["apps/etcd"], keys);
+        let records = vec![("/langs/go", go.clone()), ("/langs/golang", json!({}))];
+        let keys: Vec<&str> = super::rows(&records, "/langs/go").iter().map(|row| row.0).collect();
+        assert!(keys.is_empty());
+    }
+}
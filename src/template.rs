@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+/// Variables bound by ancestor entities
+pub type Vars = BTreeMap<String, String>;
+
+/// Primitive value as URL component, strings percent-encoded
+pub fn encode(value: &Value) -> Option<String> {
+    match value {
+        Value::String(string) => Some(urlencoding::encode(string).into_owned()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(boolean) => Some(boolean.to_string()),
+        _ => None,
+    }
+}
+
+/// Bind entity key to `{name}` and its primitive fields to `{name.field}`
+///
+/// Key is bound as is, since it is a path segment already, encoded by `encode` if taken from
+/// fields of array items
+pub fn bind(vars: &mut Vars, name: &str, key: &str, value: &Value) {
+    vars.insert(name.to_owned(), key.to_owned());
+    let fields = match value {
+        Value::Object(fields) => fields,
+        _ => return,
+    };
+    for (field, value) in fields {
+        if let Some(value) = encode(value) {
+            vars.insert(format!("{}.{}", name, field), value);
+        }
+    }
+}
+
+pub fn is_template(text: &str) -> bool {
+    text.contains('{')
+}
+
+/// Names of variables in template, `{name.field}` yielding `name`
+pub fn variables(template: &str) -> Result<Vec<&str>, String> {
+    let mut names = Vec::new();
+    let mut remain = template;
+    while let Some((_, right)) = remain.split_once('{') {
+        let (name, right) = match right.split_once('}') {
+            Some(pair) => pair,
+            None => return Err(format!("Unclosed variable in {}", template)),
+        };
+        names.push(name.split_once('.').map(|(name, _)| name).unwrap_or(name));
+        remain = right;
+    }
+    Ok(names)
+}
+
+/// Substitute `{variable}` in template
+pub fn render(template: &str, vars: &Vars) -> Result<String, String> {
+    let mut output = String::with_capacity(template.len());
+    let mut remain = template;
+    while let Some((left, right)) = remain.split_once('{') {
+        let (name, right) = match right.split_once('}') {
+            Some(pair) => pair,
+            None => return Err(format!("Unclosed variable in {}", template)),
+        };
+        match vars.get(name) {
+            Some(value) => output.extend([left, value]),
+            None => return Err(format!("Unknown variable {} in {}", name, template)),
+        }
+        remain = right;
+    }
+    output.push_str(remain);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{bind, encode, render, variables, Vars};
+
+    #[test]
+    fn test_render() {
+        let mut vars = Vars::new();
+        bind(&mut vars, "language", "rust", &json!({"GC": false, "name": "Rust lang"}));
+        let template = "/v2/languages/{language}/apps?lang={language}&gc={language.GC}";
+        let expected = "/v2/languages/rust/apps?lang=rust&gc=false";
+        assert_eq!(Ok(expected.into()), render(template, &vars));
+        assert_eq!(Ok("/Rust%20lang".into()), render("/{language.name}", &vars));
+        assert!(render("/{app}", &vars).is_err());
+        assert!(render("/{language", &vars).is_err());
+        assert_eq!(Ok(vec!["language"; 3]), variables(template));
+        assert_eq!(Some("a%2Cb%25%20c%3F%23%2F".into()), encode(&json!("a,b% c?#/")));
+    }
+}